@@ -1,6 +1,12 @@
 
+use std::convert::TryFrom;
+
 use clap::{App, Arg, SubCommand, AppSettings};
 
+const LANGUAGE_HELP: &str = "Select programming language for code generation.
+Possible values: rust, c, linux, cpp, python, sv, doc, svd, external:<command>
+The external generator <command> reads JSON intermediate representation of the register description from stdin.";
+
 const EDIT_HELP: &str = "Edit register description files using text-based user interface (TUI).
 Warning: Saving the file deletes comments from the file.";
 
@@ -37,14 +43,96 @@ pub fn parse_command_line_args() -> Config {
             .arg(Arg::with_name("output")
                 .takes_value(true)
                 .short("o")
-                .help("Output file.")
+                .help("Output file. External generators and Markdown documentation write their files to this directory.")
                 .required(true))
             .arg(Arg::with_name("language")
                 .takes_value(true)
                 .short("l")
-                .possible_values(&["rust"])
+                .validator(|value| Language::try_from(value.as_str()).map(|_| ()))
                 .default_value("rust")
-                .help("Select programming language for code generation.")))
+                .help(LANGUAGE_HELP))
+            .arg(Arg::with_name("mmio")
+                .long("mmio")
+                .help("Rust: Generate memory mapped IO implementation for absolute and relative register locations."))
+            .arg(Arg::with_name("port-io")
+                .long("port-io")
+                .help("Rust: Generate x86 port IO implementation for absolute and index register locations."))
+            .arg(Arg::with_name("mock-io")
+                .long("mock-io")
+                .help("Rust: Generate in-memory IO implementation with access log for testing."))
+            .arg(Arg::with_name("traced")
+                .long("traced")
+                .help("Rust: Generate IO adapter which reports register accesses with register and field names."))
+            .arg(Arg::with_name("fallible")
+                .long("fallible")
+                .help("Rust: Use fallible IO traits. Register read, write and modify methods return Result."))
+            .arg(Arg::with_name("async")
+                .long("async")
+                .conflicts_with("fallible")
+                .help("Rust: Generate async IO traits and async register read, write and modify methods."))
+            .arg(Arg::with_name("debug-assert-bits")
+                .long("debug-assert-bits")
+                .help("Rust: Check with debug_assert that values written with bit field bits method fit to the bit field."))
+            .arg(Arg::with_name("const-fn")
+                .long("const-fn")
+                .help("Rust: Generate const register value builders and write_value method for writing prebuilt values."))
+            .arg(Arg::with_name("snapshot")
+                .long("snapshot")
+                .help("Rust: Generate register group Snapshot with save and restore methods. With --async also save_async and restore_async methods are generated. Last written values of write-only registers are stored in the register group."))
+            .arg(Arg::with_name("shadow")
+                .long("shadow")
+                .help("Rust: Store last written values of write-only registers in the register group. Write-only registers get modify and last_written methods."))
+            .arg(Arg::with_name("serde-feature")
+                .long("serde-feature")
+                .takes_value(true)
+                .value_name("FEATURE")
+                .help("Rust: Generate serde Serialize and Deserialize implementations for register values. Implementations are enabled with cargo feature FEATURE."))
+            .arg(Arg::with_name("defmt-feature")
+                .long("defmt-feature")
+                .takes_value(true)
+                .value_name("FEATURE")
+                .help("Rust: Generate defmt Format implementations for register values. Implementations are enabled with cargo feature FEATURE."))
+            .arg(Arg::with_name("uvm")
+                .long("uvm")
+                .help("SystemVerilog: Generate UVM register model with uvm_reg classes and register blocks."))
+            .arg(Arg::with_name("doc-format")
+                .long("doc-format")
+                .takes_value(true)
+                .possible_values(&["markdown", "html"])
+                .default_value("markdown")
+                .help("Doc: Select documentation format. Markdown output is an mdBook source directory and HTML output is a single file.")))
+        .subcommand(SubCommand::with_name("export")
+            .about("Export validated register description with default values applied.")
+            .arg(Arg::with_name("input")
+                .takes_value(true)
+                .required(true)
+                .help("Input file."))
+            .arg(Arg::with_name("output")
+                .takes_value(true)
+                .short("o")
+                .help("Output file. Standard output is used by default."))
+            .arg(Arg::with_name("format")
+                .takes_value(true)
+                .long("format")
+                .possible_values(&["json", "yaml", "wavedrom", "svg", "ascii", "ipxact"])
+                .default_value("json")
+                .help("Select export format. Formats wavedrom, svg and ascii are bit field layout diagrams of the registers. Format ipxact is IP-XACT (IEEE 1685-2014) component.")))
+        .subcommand(SubCommand::with_name("import")
+            .about("Convert register descriptions from other formats to register description file. The new file is validated after the conversion.")
+            .arg(Arg::with_name("format")
+                .takes_value(true)
+                .required(true)
+                .possible_values(&["svd", "ipxact", "rdl"])
+                .help("Input file format. Format svd is CMSIS-SVD device description, format ipxact is IP-XACT (IEEE 1685-2009 or IEEE 1685-2014) component and format rdl is SystemRDL."))
+            .arg(Arg::with_name("input")
+                .takes_value(true)
+                .required(true)
+                .help("Input file."))
+            .arg(Arg::with_name("output")
+                .takes_value(true)
+                .short("o")
+                .required(true)
+                .help("Output file.")))
         .get_matches();
 
     match matches.subcommand() {
@@ -63,8 +151,62 @@ pub fn parse_command_line_args() -> Config {
         ("generate", Some(sub_m)) => {
             let input = sub_m.value_of("input").unwrap().to_owned();
             let output = sub_m.value_of("output").unwrap().to_owned();
+            let mut language = Language::try_from(sub_m.value_of("language").unwrap()).unwrap();
+            if let Language::Doc(options) = &mut language {
+                options.format = match sub_m.value_of("doc-format").unwrap() {
+                    "markdown" => DocFormat::Markdown,
+                    "html" => DocFormat::Html,
+                    _ => unreachable!(),
+                };
+            }
+            if let Language::Sv(options) = &mut language {
+                options.uvm = sub_m.is_present("uvm");
+            }
+            if let Language::Rust(options) = &mut language {
+                options.mmio = sub_m.is_present("mmio");
+                options.port_io = sub_m.is_present("port-io");
+                options.mock_io = sub_m.is_present("mock-io");
+                options.traced = sub_m.is_present("traced");
+                options.fallible = sub_m.is_present("fallible");
+                options.async_io = sub_m.is_present("async");
+                options.debug_assert_bits = sub_m.is_present("debug-assert-bits");
+                options.const_fn = sub_m.is_present("const-fn");
+                options.snapshot = sub_m.is_present("snapshot");
+                options.shadow = sub_m.is_present("shadow");
+                options.serde_feature = sub_m.value_of("serde-feature").map(|feature| feature.to_owned());
+                options.defmt_feature = sub_m.value_of("defmt-feature").map(|feature| feature.to_owned());
+            }
             Config::Generate {
-                input, output, language: Language::Rust,
+                input, output, language,
+            }
+        },
+        ("export", Some(sub_m)) => {
+            let input = sub_m.value_of("input").unwrap().to_owned();
+            let output = sub_m.value_of("output").map(|x| x.to_owned());
+            let format = match sub_m.value_of("format").unwrap() {
+                "json" => ExportFormat::Json,
+                "yaml" => ExportFormat::Yaml,
+                "wavedrom" => ExportFormat::Wavedrom,
+                "svg" => ExportFormat::Svg,
+                "ascii" => ExportFormat::Ascii,
+                "ipxact" => ExportFormat::Ipxact,
+                _ => unreachable!(),
+            };
+            Config::Export {
+                input, output, format,
+            }
+        },
+        ("import", Some(sub_m)) => {
+            let input = sub_m.value_of("input").unwrap().to_owned();
+            let output = sub_m.value_of("output").unwrap().to_owned();
+            let format = match sub_m.value_of("format").unwrap() {
+                "svd" => ImportFormat::Svd,
+                "ipxact" => ImportFormat::Ipxact,
+                "rdl" => ImportFormat::Rdl,
+                _ => unreachable!(),
+            };
+            Config::Import {
+                input, output, format,
             }
         },
         _ => unreachable!()
@@ -85,9 +227,142 @@ pub enum Config {
         input: String,
         output: String,
         language: Language,
-    }
+    },
+    Export {
+        input: String,
+        /// Standard output is used if output file is not set.
+        output: Option<String>,
+        format: ExportFormat,
+    },
+    Import {
+        input: String,
+        output: String,
+        format: ImportFormat,
+    },
 }
 
 pub enum Language {
-    Rust,
+    Rust(RustOptions),
+    /// C header.
+    C,
+    /// Linux kernel style C header.
+    Linux,
+    /// C++ header.
+    Cpp,
+    /// Python module.
+    Python,
+    /// SystemVerilog package.
+    Sv(SvOptions),
+    /// Register reference documentation.
+    Doc(DocOptions),
+    /// CMSIS-SVD device description.
+    Svd,
+    /// Executable which reads JSON intermediate representation from stdin.
+    External(String),
+}
+
+/// Optional features of the Rust code generator.
+#[derive(Debug, Default, Clone)]
+pub struct RustOptions {
+    /// Generate `Mmio` and `MmioRel` IO implementations.
+    pub mmio: bool,
+    /// Generate x86 `PortIo` IO implementation.
+    pub port_io: bool,
+    /// Generate `MockIo` IO implementation for every register group.
+    pub mock_io: bool,
+    /// Generate `Traced` IO adapter.
+    pub traced: bool,
+    /// Use `TryRegister*Io*` traits in generated register API.
+    pub fallible: bool,
+    /// Generate `register_trait_async` module and async register methods.
+    pub async_io: bool,
+    /// Add `debug_assert` for field overflow to `bits` methods of field write proxies.
+    pub debug_assert_bits: bool,
+    /// Generate const `W` builders and `write_value` register method.
+    pub const_fn: bool,
+    /// Generate `Snapshot` struct and `save` and `restore` methods for register groups.
+    pub snapshot: bool,
+    /// Generate `modify` and `last_written` methods for write-only registers.
+    pub shadow: bool,
+    /// Cargo feature which enables generated serde implementations.
+    pub serde_feature: Option<String>,
+    /// Cargo feature which enables generated defmt implementations.
+    pub defmt_feature: Option<String>,
+}
+
+/// Optional features of the SystemVerilog code generator.
+#[derive(Debug, Default, Clone)]
+pub struct SvOptions {
+    /// Generate UVM register model package.
+    pub uvm: bool,
+}
+
+/// Options of the documentation generator.
+#[derive(Debug, Default, Clone)]
+pub struct DocOptions {
+    pub format: DocFormat,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub enum DocFormat {
+    /// mdBook source directory.
+    #[default]
+    Markdown,
+    /// Standalone HTML file.
+    Html,
+}
+
+const LANGUAGE_RUST: &str = "rust";
+const LANGUAGE_C: &str = "c";
+const LANGUAGE_LINUX: &str = "linux";
+const LANGUAGE_CPP: &str = "cpp";
+const LANGUAGE_PYTHON: &str = "python";
+const LANGUAGE_SV: &str = "sv";
+const LANGUAGE_DOC: &str = "doc";
+const LANGUAGE_SVD: &str = "svd";
+const LANGUAGE_EXTERNAL_PREFIX: &str = "external:";
+
+impl TryFrom<&str> for Language {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value {
+            LANGUAGE_RUST => return Ok(Language::Rust(RustOptions::default())),
+            LANGUAGE_C => return Ok(Language::C),
+            LANGUAGE_LINUX => return Ok(Language::Linux),
+            LANGUAGE_CPP => return Ok(Language::Cpp),
+            LANGUAGE_PYTHON => return Ok(Language::Python),
+            LANGUAGE_SV => return Ok(Language::Sv(SvOptions::default())),
+            LANGUAGE_DOC => return Ok(Language::Doc(DocOptions::default())),
+            LANGUAGE_SVD => return Ok(Language::Svd),
+            _ => (),
+        }
+
+        match value.strip_prefix(LANGUAGE_EXTERNAL_PREFIX) {
+            None => Err(format!("unknown language '{}'", value)),
+            Some("") => Err(format!("external generator command is missing, expected '{}<command>'", LANGUAGE_EXTERNAL_PREFIX)),
+            Some(command) => Ok(Language::External(command.to_string())),
+        }
+    }
+}
+
+pub enum ExportFormat {
+    Json,
+    Yaml,
+    /// WaveDrom `reg` diagrams in JSON.
+    Wavedrom,
+    Svg,
+    /// Box diagrams for terminals and code comments.
+    Ascii,
+    /// IP-XACT component XML.
+    Ipxact,
+}
+
+pub enum ImportFormat {
+    /// CMSIS-SVD device description.
+    Svd,
+    /// IP-XACT component XML.
+    Ipxact,
+    /// SystemRDL source file.
+    Rdl,
 }
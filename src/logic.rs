@@ -1,10 +1,15 @@
 pub mod validation;
 pub mod codegen;
+pub mod ir;
+pub mod export;
+pub mod import;
+#[cfg(test)]
+pub mod test_data;
 
 use std::fs;
 
 use validation::{ValidationError, ParsedFile};
-use crate::config::{ Config, Language };
+use crate::config::{ Config, Language, ExportFormat, ImportFormat };
 
 pub fn run(config: Config) {
     match config {
@@ -17,6 +22,12 @@ pub fn run(config: Config) {
         Config::Generate {input, output, language } => {
             generate(input, output, language)
         }
+        Config::Export { input, output, format } => {
+            export(input, output, format)
+        }
+        Config::Import { input, output, format } => {
+            import(input, output, format)
+        }
         _ => unimplemented!()
     }
 }
@@ -74,6 +85,54 @@ fn generate(input: String, output: String, language: Language) {
     };
 
     match language {
-        Language::Rust => self::codegen::rust::parsed_file_to_rust(&parsed_file, &output)
+        Language::Rust(options) => self::codegen::rust::parsed_file_to_rust(&parsed_file, &output, &options),
+        Language::C => self::codegen::c::parsed_file_to_c(&parsed_file, &output),
+        Language::Linux => self::codegen::linux::parsed_file_to_linux(&parsed_file, &output),
+        Language::Cpp => self::codegen::cpp::parsed_file_to_cpp(&parsed_file, &output),
+        Language::Python => self::codegen::python::parsed_file_to_python(&parsed_file, &output),
+        Language::Sv(options) => self::codegen::sv::parsed_file_to_sv(&parsed_file, &output, &options),
+        Language::Doc(options) => self::codegen::doc::parsed_file_to_doc(&parsed_file, &output, &options),
+        Language::Svd => self::codegen::svd::parsed_file_to_svd(&parsed_file, &output),
+        Language::External(command) => self::codegen::external::parsed_file_to_external(&parsed_file, &command, &output),
+    }
+}
+
+fn export(input: String, output: Option<String>, format: ExportFormat) {
+    let parsed_file = match run_validation_and_print_errors(&input) {
+        Ok((parsed_file, _)) => parsed_file,
+        Err(_) => std::process::exit(-1),
+    };
+
+    self::export::parsed_file_to_export(&parsed_file, output.as_deref(), format)
+}
+
+fn import(input: String, output: String, format: ImportFormat) {
+    let text = fs::read_to_string(&input).unwrap();
+
+    let (imported_file, format_name) = match format {
+        ImportFormat::Svd => (self::import::svd::svd_to_imported_file(&text), "CMSIS-SVD"),
+        ImportFormat::Ipxact => (self::import::ipxact::ipxact_to_imported_file(&text), "IP-XACT"),
+        ImportFormat::Rdl => (self::import::rdl::rdl_to_imported_file(&text), "SystemRDL"),
+    };
+
+    let imported_file = match imported_file {
+        Ok(imported_file) => imported_file,
+        Err(errors) => {
+            for e in errors {
+                println!("error: {}", e);
+            }
+            println!("error: {} import failed", format_name);
+            std::process::exit(-1)
+        }
+    };
+
+    let source = format!("{} file '{}'", format_name, input);
+    fs::write(&output, self::import::imported_file_to_toml(&imported_file, &source)).unwrap();
+
+    // Validate immediately so that the user knows
+    // if the imported file needs manual fixes.
+    match run_validation_and_print_errors(&output) {
+        Ok(_) => println!("Imported {} file '{}' to file '{}'", format_name, input, output),
+        Err(_) => std::process::exit(-1),
     }
 }
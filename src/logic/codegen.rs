@@ -1 +1,9 @@
 pub mod rust;
+pub mod c;
+pub mod linux;
+pub mod cpp;
+pub mod python;
+pub mod sv;
+pub mod doc;
+pub mod svd;
+pub mod external;
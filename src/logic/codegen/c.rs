@@ -0,0 +1,220 @@
+//! C header code generation.
+//!
+//! Names are prefixed with the register group name, so registers with
+//! the same name in different groups don't collide.
+
+use std::{
+    fmt::Write as FmtWrite,
+    io::Write,
+    fs,
+};
+
+use inflections::Inflect;
+
+use crate::logic::validation::{
+    ParsedFile,
+    register::{
+        Register,
+        RegisterFunction,
+        RegisterEnum,
+        RegisterLocation,
+        RegisterSize,
+        AccessMode,
+    },
+};
+
+pub fn parsed_file_to_c(parsed_file: &ParsedFile, output: &str) {
+    let header = parsed_file_to_c_header(parsed_file);
+
+    let mut f = fs::File::create(output).unwrap();
+    f.write_all(header.as_bytes()).unwrap();
+}
+
+fn parsed_file_to_c_header(parsed_file: &ParsedFile) -> String {
+    let rd = &parsed_file.description;
+    let mut output = String::new();
+
+    let include_guard = format!("{}_H", rd.name.as_str().to_constant_case());
+
+    writeln!(output, "/* Generated from register description `{}` */", c_comment(rd.name.as_str())).unwrap();
+    if let Some(description) = &rd.description {
+        writeln!(output, "/* {} */", c_comment(description)).unwrap();
+    }
+    writeln!(output).unwrap();
+    writeln!(output, "#ifndef {}", include_guard).unwrap();
+    writeln!(output, "#define {}", include_guard).unwrap();
+    writeln!(output).unwrap();
+    writeln!(output, "#include <stdint.h>").unwrap();
+
+    for (group, registers) in parsed_file.register_groups() {
+        for r in registers {
+            register_to_c(&mut output, group, r);
+        }
+    }
+
+    writeln!(output).unwrap();
+    writeln!(output, "#endif /* {} */", include_guard).unwrap();
+
+    output
+}
+
+fn register_to_c(output: &mut String, group: Option<&str>, r: &Register) {
+    let prefix = register_prefix(group, r);
+    let c_type = c_type(r.size_in_bits);
+
+    writeln!(output).unwrap();
+    writeln!(output).unwrap();
+    match &r.description {
+        Some(description) => writeln!(output, "/* {} ({}) - {} */", c_comment(r.name.as_str()), r.access_mode, c_comment(description)).unwrap(),
+        None => writeln!(output, "/* {} ({}) */", c_comment(r.name.as_str()), r.access_mode).unwrap(),
+    }
+
+    for (name, value) in location_macros(r) {
+        writeln!(output, "#define {}_{} {}", prefix, name, c_integer(value, RegisterSize::Size32)).unwrap();
+    }
+
+    for f in r.functions.iter().filter(|f| f.status.is_normal()) {
+        let field_prefix = field_prefix(&prefix, f);
+        let field_function = field_prefix.to_lowercase();
+        let mask = f.range.max_value().unwrap() << f.range.lsb;
+        // Shift field value with a type which is at least as wide as int
+        // to avoid signed integer overflow after integer promotion.
+        let shift_type = match r.size_in_bits {
+            RegisterSize::Size64 => "uint64_t",
+            _ => "uint32_t",
+        };
+
+        let bits = if f.range.bit_count().get() == 1 { "Bit" } else { "Bits" };
+
+        writeln!(output).unwrap();
+        match f.description() {
+            Some(description) => writeln!(output, "/* {} - {} {} - {} */", c_comment(f.name().unwrap()), bits, f.range, c_comment(description)).unwrap(),
+            None => writeln!(output, "/* {} - {} {} */", c_comment(f.name().unwrap()), bits, f.range).unwrap(),
+        }
+        writeln!(output, "#define {}_SHIFT {}", field_prefix, f.range.lsb).unwrap();
+        writeln!(output, "#define {}_MASK {}", field_prefix, c_integer(mask, r.size_in_bits)).unwrap();
+
+        if let Some(e) = r.enums.iter().find(|e| e.range == f.range) {
+            enum_to_c(output, &field_prefix, e);
+        }
+
+        if let AccessMode::Read | AccessMode::ReadWrite = r.access_mode {
+            writeln!(output).unwrap();
+            writeln!(output, "static inline {} {}_get({} value)", c_type, field_function, c_type).unwrap();
+            writeln!(output, "{{").unwrap();
+            writeln!(output, "    return ({})((value & {p}_MASK) >> {p}_SHIFT);", c_type, p = field_prefix).unwrap();
+            writeln!(output, "}}").unwrap();
+        }
+
+        if let AccessMode::Write | AccessMode::ReadWrite = r.access_mode {
+            writeln!(output).unwrap();
+            writeln!(output, "static inline {} {}_set({} value, {} field_value)", c_type, field_function, c_type, c_type).unwrap();
+            writeln!(output, "{{").unwrap();
+            writeln!(output, "    return ({})((value & ~{p}_MASK) | ((({}) field_value << {p}_SHIFT) & {p}_MASK));", c_type, shift_type, p = field_prefix).unwrap();
+            writeln!(output, "}}").unwrap();
+        }
+    }
+}
+
+/// Enum values which fit to `int` are written as C `enum` and other
+/// values as `#define`. Value names have infix `_VAL_`, so that values
+/// don't collide with the field `_SHIFT` and `_MASK` macros.
+fn enum_to_c(output: &mut String, field_prefix: &str, e: &RegisterEnum) {
+    let fits_to_int = e.values.iter().all(|v| v.value <= i32::MAX as u64);
+
+    writeln!(output).unwrap();
+    if fits_to_int {
+        writeln!(output, "enum {} {{", field_prefix.to_lowercase()).unwrap();
+    }
+
+    for v in &e.values {
+        let name = format!("{}_VAL_{}", field_prefix, v.name.as_str().to_constant_case());
+        let comment = v.description.as_ref()
+            .map(|description| format!(" /* {} */", c_comment(description)))
+            .unwrap_or_default();
+
+        if fits_to_int {
+            writeln!(output, "    {} = {},{}", name, v.value, comment).unwrap();
+        } else {
+            writeln!(output, "#define {} {}{}", name, c_integer(v.value, RegisterSize::Size64), comment).unwrap();
+        }
+    }
+
+    if fits_to_int {
+        writeln!(output, "}};").unwrap();
+    }
+}
+
+/// Location macro names without register prefix and location values.
+/// Write location is only included if it is different from read location.
+pub fn location_macros(r: &Register) -> Vec<(String, u64)> {
+    let location = |location: RegisterLocation, postfix: &str| {
+        match location {
+            RegisterLocation::Index(value) => (format!("INDEX{}", postfix), value),
+            RegisterLocation::Absolute(value) => (format!("ABS_ADDRESS{}", postfix), value),
+            RegisterLocation::Relative(value) => (format!("REL_ADDRESS{}", postfix), value),
+        }
+    };
+
+    let mut macros = vec![location(r.read_location, "")];
+    if r.read_location != r.write_location {
+        macros.push(location(r.write_location, "_W"));
+    }
+
+    macros
+}
+
+/// Constant case register name prefixed with the group name.
+pub fn register_prefix(group: Option<&str>, r: &Register) -> String {
+    match group {
+        Some(group) => format!("{}_{}", group.to_constant_case(), r.name.as_str().to_constant_case()),
+        None => r.name.as_str().to_constant_case(),
+    }
+}
+
+fn field_prefix(register_prefix: &str, f: &RegisterFunction) -> String {
+    format!("{}_{}", register_prefix, f.name().unwrap().to_constant_case())
+}
+
+pub fn c_type(size: RegisterSize) -> &'static str {
+    match size {
+        RegisterSize::Size8 => "uint8_t",
+        RegisterSize::Size16 => "uint16_t",
+        RegisterSize::Size32 => "uint32_t",
+        RegisterSize::Size64 => "uint64_t",
+    }
+}
+
+/// Unsigned hexadecimal integer literal. Values of 64-bit registers
+/// and values larger than `u32::MAX` have suffix `ULL`.
+pub fn c_integer(value: u64, size: RegisterSize) -> String {
+    if size == RegisterSize::Size64 || value > u64::from(u32::MAX) {
+        format!("{:#x}ULL", value)
+    } else {
+        format!("{:#x}U", value)
+    }
+}
+
+/// Text which can't end the comment.
+pub fn c_comment(text: &str) -> String {
+    text.replace("*/", "* /").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::test_data;
+
+    #[test]
+    fn header() {
+        let header = parsed_file_to_c_header(&test_data::parsed_file(test_data::DEVICE));
+
+        assert!(header.contains("#define TIMER_CONTROL_ABS_ADDRESS 0x40000000U\n"));
+        assert!(header.contains("#define TIMER_CONTROL_MODE_MASK 0xf0U\n"));
+        assert!(header.contains("    TIMER_CONTROL_MODE_VAL_ONE_SHOT = 0, /* Stop after one period */\n"));
+        assert!(header.contains("static inline uint16_t timer_count_value_get(uint16_t value)\n"));
+        assert!(!header.contains("timer_count_value_set"));
+        assert!(!header.contains("uart_data_byte_get"));
+        assert!(header.ends_with("#endif /* DEVICE_H */\n"));
+    }
+}
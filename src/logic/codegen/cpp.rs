@@ -0,0 +1,373 @@
+//! C++ header code generation.
+//!
+//! Every register group has its own namespace containing register
+//! location constants, a value type for every register and class template
+//! `Registers<Io>` for register access. The generated header requires C++14.
+
+use std::{
+    fmt::Write as FmtWrite,
+    io::Write,
+    fs,
+};
+
+use inflections::Inflect;
+
+use crate::logic::validation::{
+    ParsedFile,
+    register::{
+        Register,
+        RegisterFunction,
+        RegisterEnum,
+        RegisterLocation,
+        RegisterSize,
+        AccessMode,
+    },
+    register_description::AddressSize,
+};
+
+use super::c::{
+    c_type,
+    c_integer,
+};
+
+const IO_DOC: &str = "\
+// Type `Io` of `Registers<Io>` must have the following member function
+// templates. Template parameter `Group` is the register group tag type
+// and `U` is the register value type. Only functions which are used by
+// the register group are required.
+//
+//     template <typename Group, typename U> U read_index(index_type index);
+//     template <typename Group, typename U> void write_index(index_type index, U value);
+//     template <typename Group, typename U> U read_abs(address_type abs_address);
+//     template <typename Group, typename U> void write_abs(address_type abs_address, U value);
+//     template <typename Group, typename U> U read_rel(address_type rel_address);
+//     template <typename Group, typename U> void write_rel(address_type rel_address, U value);
+";
+
+pub fn parsed_file_to_cpp(parsed_file: &ParsedFile, output: &str) {
+    let errors = check_enum_names(parsed_file);
+    if !errors.is_empty() {
+        for e in errors {
+            println!("error: {}", e);
+        }
+        println!("error: C++ code generation failed");
+        std::process::exit(-1)
+    }
+
+    let header = parsed_file_to_cpp_header(parsed_file);
+
+    let mut f = fs::File::create(output).unwrap();
+    f.write_all(header.as_bytes()).unwrap();
+}
+
+/// Enum classes are named after the enums and they are nested in the
+/// register value type, so enum names must be unique in the register
+/// and different from the register name.
+fn check_enum_names(parsed_file: &ParsedFile) -> Vec<String> {
+    let mut errors = vec![];
+
+    for r in parsed_file.all_registers() {
+        let register_type = r.name.as_str().to_pascal_case();
+        let mut enum_types: Vec<String> = vec![];
+
+        for e in &r.enums {
+            let enum_type = e.name.as_str().to_pascal_case();
+            if enum_type == register_type {
+                errors.push(format!("enum '{}' of register '{}' has the same C++ name as the register", e.name, r.name));
+            } else if enum_types.contains(&enum_type) {
+                errors.push(format!("register '{}' has multiple enums with C++ name '{}'", r.name, enum_type));
+            }
+            enum_types.push(enum_type);
+        }
+    }
+
+    errors
+}
+
+fn parsed_file_to_cpp_header(parsed_file: &ParsedFile) -> String {
+    let rd = &parsed_file.description;
+    let mut output = String::new();
+
+    let include_guard = format!("{}_HPP", rd.name.as_str().to_constant_case());
+    let address_type = match rd.address_size {
+        AddressSize::Pointer => "std::uintptr_t".to_string(),
+        AddressSize::RegisterSize(size) => cpp_type(size),
+    };
+
+    writeln!(output, "// Generated from register description `{}`", comment(rd.name.as_str())).unwrap();
+    if let Some(description) = &rd.description {
+        writeln!(output, "// {}", comment(description)).unwrap();
+    }
+    writeln!(output, "//").unwrap();
+    output.push_str(IO_DOC);
+    writeln!(output).unwrap();
+    writeln!(output, "#ifndef {}", include_guard).unwrap();
+    writeln!(output, "#define {}", include_guard).unwrap();
+    writeln!(output).unwrap();
+    writeln!(output, "#include <cstdint>").unwrap();
+    writeln!(output).unwrap();
+    writeln!(output, "namespace {} {{", rd.name.as_str().to_snake_case()).unwrap();
+    writeln!(output).unwrap();
+    writeln!(output, "using index_type = {};", cpp_type(rd.index_size)).unwrap();
+    writeln!(output, "using address_type = {};", address_type).unwrap();
+
+    for (group, registers) in parsed_file.register_groups() {
+        if let Some(group) = group {
+            writeln!(output).unwrap();
+            writeln!(output, "namespace {} {{", group.to_snake_case()).unwrap();
+        }
+
+        group_to_cpp(&mut output, registers);
+
+        if let Some(group) = group {
+            writeln!(output).unwrap();
+            writeln!(output, "}} // namespace {}", group.to_snake_case()).unwrap();
+        }
+    }
+
+    writeln!(output).unwrap();
+    writeln!(output, "}} // namespace {}", rd.name.as_str().to_snake_case()).unwrap();
+    writeln!(output).unwrap();
+    writeln!(output, "#endif // {}", include_guard).unwrap();
+
+    output
+}
+
+fn group_to_cpp(output: &mut String, registers: &[Register]) {
+    writeln!(output).unwrap();
+    writeln!(output, "// Register group tag type for IO functions").unwrap();
+    writeln!(output, "struct Group {{}};").unwrap();
+
+    writeln!(output).unwrap();
+    for r in registers {
+        for (name, value_type, value) in location_constants(r) {
+            writeln!(output, "constexpr {} {} = {};", value_type, name, c_integer(value, RegisterSize::Size32)).unwrap();
+        }
+    }
+
+    for r in registers {
+        register_value_type(output, r);
+    }
+
+    writeln!(output).unwrap();
+    writeln!(output, "template <typename Io>").unwrap();
+    writeln!(output, "class Registers {{").unwrap();
+    writeln!(output, "public:").unwrap();
+    writeln!(output, "    explicit Registers(Io& io) : io_(io) {{}}").unwrap();
+    writeln!(output).unwrap();
+    writeln!(output, "    Io& io() {{ return io_; }}").unwrap();
+
+    for r in registers {
+        register_access_methods(output, r);
+    }
+
+    writeln!(output).unwrap();
+    writeln!(output, "private:").unwrap();
+    writeln!(output, "    Io& io_;").unwrap();
+    writeln!(output, "}};").unwrap();
+}
+
+fn register_value_type(output: &mut String, r: &Register) {
+    let name = r.name.as_str().to_pascal_case();
+
+    writeln!(output).unwrap();
+    writeln!(output, "// {}", register_comment(r)).unwrap();
+    writeln!(output, "class {} {{", name).unwrap();
+    writeln!(output, "public:").unwrap();
+    writeln!(output, "    using value_type = {};", cpp_type(r.size_in_bits)).unwrap();
+
+    for f in r.functions.iter().filter(|f| f.status.is_normal()) {
+        if let Some(e) = r.enums.iter().find(|e| e.range == f.range) {
+            enum_to_cpp(output, f, e);
+        }
+    }
+
+    writeln!(output).unwrap();
+    writeln!(output, "    constexpr {}() = default;", name).unwrap();
+    writeln!(output, "    constexpr explicit {}(value_type raw) : raw_(raw) {{}}", name).unwrap();
+    writeln!(output).unwrap();
+    writeln!(output, "    constexpr value_type raw() const {{ return raw_; }}").unwrap();
+    writeln!(output).unwrap();
+    writeln!(output, "    constexpr bool operator==(const {}& other) const {{ return raw_ == other.raw_; }}", name).unwrap();
+    writeln!(output, "    constexpr bool operator!=(const {}& other) const {{ return raw_ != other.raw_; }}", name).unwrap();
+
+    for f in r.functions.iter().filter(|f| f.status.is_normal()) {
+        field_methods(output, r, f, &name);
+    }
+
+    writeln!(output).unwrap();
+    writeln!(output, "private:").unwrap();
+    writeln!(output, "    value_type raw_ = 0;").unwrap();
+    writeln!(output, "}};").unwrap();
+}
+
+fn enum_to_cpp(output: &mut String, f: &RegisterFunction, e: &RegisterEnum) {
+    writeln!(output).unwrap();
+    if let Some(description) = &e.description {
+        writeln!(output, "    // {}", comment(description)).unwrap();
+    }
+    writeln!(output, "    enum class {} : {} {{", e.name.as_str().to_pascal_case(), field_integer_type(f)).unwrap();
+
+    for v in &e.values {
+        let value = c_integer(v.value, RegisterSize::Size32);
+        match &v.description {
+            Some(description) => writeln!(output, "        {} = {}, // {}", v.name.as_str().to_pascal_case(), value, comment(description)).unwrap(),
+            None => writeln!(output, "        {} = {},", v.name.as_str().to_pascal_case(), value).unwrap(),
+        }
+    }
+
+    writeln!(output, "    }};").unwrap();
+}
+
+fn field_methods(output: &mut String, r: &Register, f: &RegisterFunction, register_type: &str) {
+    let field_name = f.name().unwrap().to_snake_case();
+    let mask = c_integer(f.range.max_value().unwrap() << f.range.lsb, r.size_in_bits);
+    let lsb = f.range.lsb;
+    // Shift field value with a type which is at least as wide as int
+    // to avoid signed integer overflow after integer promotion.
+    let shift_type = match r.size_in_bits {
+        RegisterSize::Size64 => "std::uint64_t",
+        _ => "std::uint32_t",
+    };
+
+    let field_enum = r.enums.iter().find(|e| e.range == f.range);
+    let field_type = if let Some(e) = field_enum {
+        e.name.as_str().to_pascal_case()
+    } else if f.range.bit_count().get() == 1 {
+        "bool".to_string()
+    } else {
+        field_integer_type(f)
+    };
+
+    let bits = if f.range.bit_count().get() == 1 { "Bit" } else { "Bits" };
+
+    writeln!(output).unwrap();
+    match f.description() {
+        Some(description) => writeln!(output, "    // {} - {} {} - {}", comment(f.name().unwrap()), bits, f.range, comment(description)).unwrap(),
+        None => writeln!(output, "    // {} - {} {}", comment(f.name().unwrap()), bits, f.range).unwrap(),
+    }
+
+    if field_type == "bool" {
+        writeln!(output, "    constexpr bool {}() const {{ return (raw_ & {}) != 0; }}", field_name, mask).unwrap();
+    } else {
+        writeln!(output, "    constexpr {} {}() const {{ return static_cast<{}>((raw_ & {}) >> {}); }}", field_type, field_name, field_type, mask, lsb).unwrap();
+    }
+
+    writeln!(output, "    constexpr {}& set_{}({} value) {{", register_type, field_name, field_type).unwrap();
+    writeln!(output, "        raw_ = static_cast<value_type>((raw_ & ~{m}) | ((static_cast<{}>(value) << {}) & {m}));", shift_type, lsb, m = mask).unwrap();
+    writeln!(output, "        return *this;").unwrap();
+    writeln!(output, "    }}").unwrap();
+}
+
+fn register_access_methods(output: &mut String, r: &Register) {
+    let name = r.name.as_str().to_snake_case();
+    let value_type = r.name.as_str().to_pascal_case();
+    let constants = location_constants(r);
+    let (read_constant, _, _) = &constants[0];
+    let (write_constant, _, _) = constants.last().unwrap();
+
+    writeln!(output).unwrap();
+    writeln!(output, "    // {}", register_comment(r)).unwrap();
+
+    if let AccessMode::Read | AccessMode::ReadWrite = r.access_mode {
+        let io_function = io_function(r.read_location);
+        writeln!(output, "    {} read_{}() {{", value_type, name).unwrap();
+        writeln!(output, "        return {}(io_.template read_{}<Group, {}::value_type>({}));", value_type, io_function, value_type, read_constant).unwrap();
+        writeln!(output, "    }}").unwrap();
+    }
+
+    if let AccessMode::Write | AccessMode::ReadWrite = r.access_mode {
+        let io_function = io_function(r.write_location);
+        writeln!(output, "    void write_{}({} value) {{", name, value_type).unwrap();
+        writeln!(output, "        io_.template write_{}<Group, {}::value_type>({}, value.raw());", io_function, value_type, write_constant).unwrap();
+        writeln!(output, "    }}").unwrap();
+    }
+
+    if r.access_mode == AccessMode::ReadWrite {
+        writeln!(output, "    template <typename F>").unwrap();
+        writeln!(output, "    void modify_{}(F f) {{", name).unwrap();
+        writeln!(output, "        {} value = read_{}();", value_type, name).unwrap();
+        writeln!(output, "        f(value);").unwrap();
+        writeln!(output, "        write_{}(value);", name).unwrap();
+        writeln!(output, "    }}").unwrap();
+    }
+}
+
+/// Constant names, types and values of register locations. Write location
+/// is only included if it is different from read location.
+fn location_constants(r: &Register) -> Vec<(String, &'static str, u64)> {
+    let name = r.name.as_str().to_snake_case();
+    let location = |location: RegisterLocation, postfix: &str| {
+        match location {
+            RegisterLocation::Index(value) => (format!("{}_index{}", name, postfix), "index_type", value),
+            RegisterLocation::Absolute(value) => (format!("{}_abs_address{}", name, postfix), "address_type", value),
+            RegisterLocation::Relative(value) => (format!("{}_rel_address{}", name, postfix), "address_type", value),
+        }
+    };
+
+    let mut constants = vec![location(r.read_location, "")];
+    if r.read_location != r.write_location {
+        constants.push(location(r.write_location, "_w"));
+    }
+
+    constants
+}
+
+fn io_function(location: RegisterLocation) -> &'static str {
+    match location {
+        RegisterLocation::Index(_) => "index",
+        RegisterLocation::Absolute(_) => "abs",
+        RegisterLocation::Relative(_) => "rel",
+    }
+}
+
+fn register_comment(r: &Register) -> String {
+    match &r.description {
+        Some(description) => format!("{} ({}) - {}", comment(r.name.as_str()), r.access_mode, comment(description)),
+        None => format!("{} ({})", comment(r.name.as_str()), r.access_mode),
+    }
+}
+
+/// Smallest unsigned integer type for field values.
+fn field_integer_type(f: &RegisterFunction) -> String {
+    let size = match f.range.bit_count().get() {
+        0..=8 => RegisterSize::Size8,
+        9..=16 => RegisterSize::Size16,
+        17..=32 => RegisterSize::Size32,
+        _ => RegisterSize::Size64,
+    };
+
+    cpp_type(size)
+}
+
+fn cpp_type(size: RegisterSize) -> String {
+    format!("std::{}", c_type(size))
+}
+
+fn comment(text: &str) -> String {
+    text.replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::test_data;
+
+    #[test]
+    fn header() {
+        let header = parsed_file_to_cpp_header(&test_data::parsed_file(test_data::DEVICE));
+
+        assert!(header.contains("namespace timer {\n"));
+        assert!(header.contains("constexpr address_type control_abs_address = 0x40000000U;\n"));
+        assert!(header.contains("    enum class Mode : std::uint8_t {\n"));
+        assert!(header.contains("    constexpr Mode mode() const {"));
+        assert!(header.contains("    constexpr Control& set_prescaler(std::uint8_t value) {\n"));
+    }
+
+    #[test]
+    fn enum_with_register_name_is_error() {
+        let text = test_data::DEVICE.replace("name = \"Mode\"\nbit", "name = \"Control\"\nbit");
+        let errors = check_enum_names(&test_data::parsed_file(&text));
+        assert_eq!(errors, vec!["enum 'Control' of register 'Control' has the same C++ name as the register".to_string()]);
+    }
+}
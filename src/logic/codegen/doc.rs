@@ -0,0 +1,472 @@
+//! Register reference documentation generation.
+//!
+//! Markdown output is an mdBook source directory with `SUMMARY.md`,
+//! `index.md` and a page for every register. HTML output is a single
+//! standalone file.
+//!
+//! Anchors are derived from group, register and field names, so links
+//! stay valid when registers are added or reordered. Anchors are unique in
+//! the document. Anchor which is already used or which is one of the
+//! reserved anchors `index`, `summary` and `contents` gets a number postfix.
+
+use std::{
+    collections::HashSet,
+    fmt::Write as FmtWrite,
+    fs,
+    path::Path,
+};
+
+use inflections::Inflect;
+
+use crate::{
+    config::{DocOptions, DocFormat},
+    logic::validation::{
+        ParsedFile,
+        register::{
+            Register,
+            RegisterFunction,
+            RegisterEnum,
+            RegisterLocation,
+            AccessMode,
+        },
+    },
+};
+
+const HTML_STYLE: &str = "\
+body { font-family: sans-serif; max-width: 60em; margin: auto; padding: 1em; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+th, td { border: 1px solid #ccc; padding: 0.25em 0.5em; text-align: left; vertical-align: top; }
+code { font-size: 1.1em; }
+";
+
+pub fn parsed_file_to_doc(parsed_file: &ParsedFile, output: &str, options: &DocOptions) {
+    let result = match options.format {
+        DocFormat::Markdown => write_markdown(parsed_file, output),
+        DocFormat::Html => fs::write(output, html_document(parsed_file)).map_err(|e| e.to_string()),
+    };
+
+    if let Err(e) = result {
+        println!("error: writing documentation to '{}' failed: {}", output, e);
+        std::process::exit(-1)
+    }
+}
+
+/// Markup of the output format.
+trait Markup {
+    fn escape(&self, text: &str) -> String;
+    fn heading(&self, level: usize, text: &str, anchor: &str) -> String;
+    fn paragraph(&self, text: &str) -> String;
+    fn code(&self, text: &str) -> String;
+    fn link(&self, text: &str, href: &str) -> String;
+    /// Anchor which is placed before the text.
+    fn anchor(&self, anchor: &str, text: &str) -> String;
+    fn table(&self, header: &[&str], rows: &[Vec<String>]) -> String;
+    /// Link target of register `register_anchor`.
+    fn register_href(&self, register_anchor: &str) -> String;
+}
+
+struct Markdown;
+
+impl Markup for Markdown {
+    fn escape(&self, text: &str) -> String {
+        let mut escaped = String::new();
+        for c in text.chars() {
+            match c {
+                '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|' | '#' => {
+                    escaped.push('\\');
+                    escaped.push(c);
+                }
+                '\n' => escaped.push_str("<br>"),
+                c => escaped.push(c),
+            }
+        }
+        escaped
+    }
+
+    fn heading(&self, level: usize, text: &str, anchor: &str) -> String {
+        format!("<a id=\"{}\"></a>\n\n{} {}\n\n", anchor, "#".repeat(level), text)
+    }
+
+    fn paragraph(&self, text: &str) -> String {
+        format!("{}\n\n", text)
+    }
+
+    fn code(&self, text: &str) -> String {
+        format!("`{}`", text)
+    }
+
+    fn link(&self, text: &str, href: &str) -> String {
+        format!("[{}]({})", text, href)
+    }
+
+    fn anchor(&self, anchor: &str, text: &str) -> String {
+        format!("<a id=\"{}\"></a>{}", anchor, text)
+    }
+
+    fn table(&self, header: &[&str], rows: &[Vec<String>]) -> String {
+        let mut table = String::new();
+        writeln!(table, "| {} |", header.join(" | ")).unwrap();
+        writeln!(table, "|{}", "---|".repeat(header.len())).unwrap();
+        for row in rows {
+            writeln!(table, "| {} |", row.join(" | ")).unwrap();
+        }
+        writeln!(table).unwrap();
+        table
+    }
+
+    fn register_href(&self, register_anchor: &str) -> String {
+        format!("{}.md#{}", register_anchor, register_anchor)
+    }
+}
+
+struct Html;
+
+impl Markup for Html {
+    fn escape(&self, text: &str) -> String {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;")
+            .replace('\n', "<br>")
+    }
+
+    fn heading(&self, level: usize, text: &str, anchor: &str) -> String {
+        format!("<h{} id=\"{}\">{}</h{}>\n", level, anchor, text, level)
+    }
+
+    fn paragraph(&self, text: &str) -> String {
+        format!("<p>{}</p>\n", text)
+    }
+
+    fn code(&self, text: &str) -> String {
+        format!("<code>{}</code>", text)
+    }
+
+    fn link(&self, text: &str, href: &str) -> String {
+        format!("<a href=\"{}\">{}</a>", href, text)
+    }
+
+    fn anchor(&self, anchor: &str, text: &str) -> String {
+        format!("<span id=\"{}\">{}</span>", anchor, text)
+    }
+
+    fn table(&self, header: &[&str], rows: &[Vec<String>]) -> String {
+        let mut table = String::new();
+        writeln!(table, "<table>").unwrap();
+        writeln!(table, "<tr>{}</tr>", header.iter().map(|h| format!("<th>{}</th>", h)).collect::<String>()).unwrap();
+        for row in rows {
+            writeln!(table, "<tr>{}</tr>", row.iter().map(|cell| format!("<td>{}</td>", cell)).collect::<String>()).unwrap();
+        }
+        writeln!(table, "</table>").unwrap();
+        table
+    }
+
+    fn register_href(&self, register_anchor: &str) -> String {
+        format!("#{}", register_anchor)
+    }
+}
+
+/// Anchors of the register table of contents and reserved
+/// Markdown page names.
+const RESERVED_ANCHORS: &[&str] = &["index", "summary", "contents"];
+
+/// Unique anchors of the document in the same order as
+/// `ParsedFile::register_groups`.
+struct Anchors {
+    groups: Vec<Option<String>>,
+    registers: Vec<Vec<RegisterAnchors>>,
+}
+
+struct RegisterAnchors {
+    register: String,
+    bit_fields: String,
+    /// Field anchors in the same order as register functions.
+    /// Reserved fields don't have an anchor.
+    fields: Vec<Option<String>>,
+    /// Anchors of field value tables.
+    values: Vec<Option<String>>,
+}
+
+impl Anchors {
+    fn new(parsed_file: &ParsedFile) -> Self {
+        let mut used: HashSet<String> = RESERVED_ANCHORS.iter().map(|a| a.to_string()).collect();
+        let mut unique = |anchor: String| {
+            let mut candidate = anchor.clone();
+            let mut number = 2;
+            while !used.insert(candidate.clone()) {
+                candidate = format!("{}-{}", anchor, number);
+                number += 1;
+            }
+            candidate
+        };
+
+        let mut groups = vec![];
+        let mut registers = vec![];
+
+        for (group, group_registers) in parsed_file.register_groups() {
+            groups.push(group.map(|group| unique(group.to_kebab_case())));
+
+            registers.push(group_registers.iter().map(|r| {
+                let register = match group {
+                    Some(group) => unique(format!("{}-{}", group.to_kebab_case(), r.name.as_str().to_kebab_case())),
+                    None => unique(r.name.as_str().to_kebab_case()),
+                };
+                let bit_fields = unique(format!("{}-fields", register));
+                let fields: Vec<Option<String>> = r.functions.iter()
+                    .map(|f| f.name().map(|name| unique(format!("{}-{}", register, name.to_kebab_case()))))
+                    .collect();
+                let values = r.functions.iter().zip(&fields)
+                    .map(|(f, field)| match field {
+                        Some(field) if r.enums.iter().any(|e| e.range == f.range) => Some(unique(format!("{}-values", field))),
+                        _ => None,
+                    })
+                    .collect();
+
+                RegisterAnchors { register, bit_fields, fields, values }
+            }).collect());
+        }
+
+        Anchors { groups, registers }
+    }
+}
+
+fn write_markdown(parsed_file: &ParsedFile, output_dir: &str) -> Result<(), String> {
+    let output_dir = Path::new(output_dir);
+    fs::create_dir_all(output_dir).map_err(|e| e.to_string())?;
+
+    let anchors = Anchors::new(parsed_file);
+    let rd = &parsed_file.description;
+    let mut summary = String::new();
+    writeln!(summary, "# Summary").unwrap();
+    writeln!(summary).unwrap();
+    writeln!(summary, "[{}](index.md)", Markdown.escape(rd.name.as_str())).unwrap();
+
+    for ((group, registers), register_anchors) in parsed_file.register_groups().into_iter().zip(&anchors.registers) {
+        writeln!(summary).unwrap();
+        if let Some(group) = group {
+            writeln!(summary, "# {}", Markdown.escape(&group.to_title_case())).unwrap();
+            writeln!(summary).unwrap();
+        }
+
+        for (r, a) in registers.iter().zip(register_anchors) {
+            writeln!(summary, "- [{}]({}.md)", Markdown.escape(r.name.as_str()), a.register).unwrap();
+
+            let page = register_section(&Markdown, group, r, a, 1);
+            fs::write(output_dir.join(format!("{}.md", a.register)), page).map_err(|e| e.to_string())?;
+        }
+    }
+
+    fs::write(output_dir.join("SUMMARY.md"), summary).map_err(|e| e.to_string())?;
+    fs::write(output_dir.join("index.md"), table_of_contents(&Markdown, parsed_file, &anchors, 1)).map_err(|e| e.to_string())
+}
+
+fn html_document(parsed_file: &ParsedFile) -> String {
+    let anchors = Anchors::new(parsed_file);
+    let title = Html.escape(parsed_file.description.name.as_str());
+    let mut output = String::new();
+
+    writeln!(output, "<!DOCTYPE html>").unwrap();
+    writeln!(output, "<html>").unwrap();
+    writeln!(output, "<head>").unwrap();
+    writeln!(output, "<meta charset=\"utf-8\">").unwrap();
+    writeln!(output, "<title>{}</title>", title).unwrap();
+    writeln!(output, "<style>\n{}</style>", HTML_STYLE).unwrap();
+    writeln!(output, "</head>").unwrap();
+    writeln!(output, "<body>").unwrap();
+    output.push_str(&table_of_contents(&Html, parsed_file, &anchors, 1));
+
+    for ((group, registers), register_anchors) in parsed_file.register_groups().into_iter().zip(&anchors.registers) {
+        for (r, a) in registers.iter().zip(register_anchors) {
+            output.push_str(&register_section(&Html, group, r, a, 2));
+        }
+    }
+
+    writeln!(output, "</body>").unwrap();
+    writeln!(output, "</html>").unwrap();
+
+    output
+}
+
+/// Title, description and register table for every register group.
+fn table_of_contents<M: Markup>(m: &M, parsed_file: &ParsedFile, anchors: &Anchors, level: usize) -> String {
+    let rd = &parsed_file.description;
+    let mut output = m.heading(level, &m.escape(rd.name.as_str()), "contents");
+
+    if let Some(description) = &rd.description {
+        output.push_str(&m.paragraph(&m.escape(description)));
+    }
+
+    let groups = parsed_file.register_groups().into_iter().zip(&anchors.groups).zip(&anchors.registers);
+    for (((group, registers), group_anchor), register_anchors) in groups {
+        if let (Some(group), Some(group_anchor)) = (group, group_anchor) {
+            let group_name = m.escape(&group.to_title_case());
+            output.push_str(&m.heading(level + 1, &group_name, group_anchor));
+        }
+
+        let rows: Vec<Vec<String>> = registers.iter().zip(register_anchors).map(|(r, a)| {
+            vec![
+                m.link(&m.escape(r.name.as_str()), &m.register_href(&a.register)),
+                location_text(m, r),
+                access_text(r.access_mode).to_string(),
+                m.escape(r.description.as_deref().unwrap_or("")),
+            ]
+        }).collect();
+
+        output.push_str(&m.table(&["Register", "Location", "Access", "Description"], &rows));
+    }
+
+    output
+}
+
+fn register_section<M: Markup>(m: &M, group: Option<&str>, r: &Register, a: &RegisterAnchors, level: usize) -> String {
+    let mut output = m.heading(level, &m.escape(r.name.as_str()), &a.register);
+
+    if let Some(description) = &r.description {
+        output.push_str(&m.paragraph(&m.escape(description)));
+    }
+
+    let mut properties = vec![];
+    if let Some(group) = group {
+        properties.push(vec!["Group".to_string(), m.escape(&group.to_title_case())]);
+    }
+    properties.push(vec!["Location".to_string(), location_text(m, r)]);
+    properties.push(vec!["Size".to_string(), format!("{} bits", r.size_in_bits)]);
+    properties.push(vec!["Access".to_string(), access_text(r.access_mode).to_string()]);
+    output.push_str(&m.table(&["Property", "Value"], &properties));
+
+    let rows: Vec<Vec<String>> = r.functions.iter().zip(a.fields.iter().zip(&a.values))
+        .map(|(f, (field, values))| field_row(m, f, field.as_deref(), values.as_deref()))
+        .collect();
+    output.push_str(&m.heading(level + 1, "Bit fields", &a.bit_fields));
+    output.push_str(&m.table(&["Bits", "Name", "Description"], &rows));
+
+    for (f, values) in r.functions.iter().zip(&a.values) {
+        if let (Some(e), Some(values)) = (r.enums.iter().find(|e| e.range == f.range), values) {
+            output.push_str(&enum_table(m, values, f, e, level + 1));
+        }
+    }
+
+    output
+}
+
+/// Fields don't have their own access mode, so the access of
+/// the register is only listed in the register properties.
+fn field_row<M: Markup>(m: &M, f: &RegisterFunction, anchor: Option<&str>, values_anchor: Option<&str>) -> Vec<String> {
+    let (name, anchor) = match (f.name(), anchor) {
+        (Some(name), Some(anchor)) => (name, anchor),
+        _ => return vec![f.range.to_string(), "Reserved".to_string(), String::new()],
+    };
+
+    let mut description = m.escape(f.description().unwrap_or(""));
+
+    if let Some(values_anchor) = values_anchor {
+        if !description.is_empty() {
+            description.push(' ');
+        }
+        description.push_str(&m.link("Values", &format!("#{}", values_anchor)));
+    }
+
+    vec![
+        f.range.to_string(),
+        m.anchor(anchor, &m.escape(name)),
+        description,
+    ]
+}
+
+fn enum_table<M: Markup>(m: &M, anchor: &str, f: &RegisterFunction, e: &RegisterEnum, level: usize) -> String {
+    let title = format!("{} values", m.escape(f.name().unwrap()));
+    let mut output = m.heading(level, &title, anchor);
+
+    if let Some(description) = &e.description {
+        output.push_str(&m.paragraph(&m.escape(description)));
+    }
+
+    let rows: Vec<Vec<String>> = e.values.iter().map(|v| {
+        vec![
+            m.code(&format!("{:#x}", v.value)),
+            m.escape(v.name.as_str()),
+            m.escape(v.description.as_deref().unwrap_or("")),
+        ]
+    }).collect();
+
+    output.push_str(&m.table(&["Value", "Name", "Description"], &rows));
+    output
+}
+
+/// Read and write locations are listed separately if they are different.
+fn location_text<M: Markup>(m: &M, r: &Register) -> String {
+    let location = |location: RegisterLocation| {
+        match location {
+            RegisterLocation::Index(value) => format!("Index {}", m.code(&format!("{:#x}", value))),
+            RegisterLocation::Absolute(value) => format!("Absolute address {}", m.code(&format!("{:#x}", value))),
+            RegisterLocation::Relative(value) => format!("Relative address {}", m.code(&format!("{:#x}", value))),
+        }
+    };
+
+    if r.read_location == r.write_location {
+        location(r.read_location)
+    } else {
+        format!("Read: {}<br>Write: {}", location(r.read_location), location(r.write_location))
+    }
+}
+
+fn access_text(access_mode: AccessMode) -> &'static str {
+    match access_mode {
+        AccessMode::Read => "Read-only",
+        AccessMode::Write => "Write-only",
+        AccessMode::ReadWrite => "Read-write",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::test_data;
+
+    #[test]
+    fn html() {
+        let html = html_document(&test_data::parsed_file(test_data::DEVICE));
+
+        assert!(html.contains("<h1 id=\"contents\">Device</h1>\n"));
+        assert!(html.contains("<td><a href=\"#timer-control\">Control</a></td><td>Absolute address <code>0x40000000</code></td>"));
+        assert!(html.contains("<h2 id=\"timer-control\">Control</h2>\n"));
+        assert!(html.contains("<a href=\"#timer-control-mode-values\">Values</a>"));
+        assert!(html.contains("<h3 id=\"timer-control-mode-values\">Mode values</h3>\n"));
+    }
+
+    #[test]
+    fn markdown_page_names_are_unique() {
+        let text = r#"
+[register_description]
+name = "Chip"
+version = "0.1"
+default_register_size = "8"
+default_register_access = "rw"
+extension = "vga"
+index_size = "8"
+address_size = "16"
+
+[[register]]
+name = "Index"
+index = 0
+bit_fields = [ { bit = "7:0", name = "Value" } ]
+
+[[register]]
+name = "Summary"
+index = 1
+bit_fields = [ { bit = "7:0", name = "Value" } ]
+"#;
+        let output_dir = std::env::temp_dir().join(format!("register-description-tools-doc-{}", std::process::id()));
+        write_markdown(&test_data::parsed_file(text), output_dir.to_str().unwrap()).unwrap();
+
+        let mut files: Vec<String> = fs::read_dir(&output_dir).unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort();
+        let summary = fs::read_to_string(output_dir.join("SUMMARY.md")).unwrap();
+        fs::remove_dir_all(&output_dir).unwrap();
+
+        assert_eq!(files, vec!["SUMMARY.md", "index-2.md", "index.md", "summary-2.md"]);
+        assert!(summary.contains("- [Index](index-2.md)\n"));
+    }
+}
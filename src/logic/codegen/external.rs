@@ -0,0 +1,102 @@
+//! Code generation with external generator executables.
+//!
+//! The external generator reads JSON intermediate representation
+//! (see module `logic::ir`) from stdin and writes a JSON object
+//! describing the generated files to stdout:
+//!
+//! ```text
+//! {
+//!   "files": [
+//!     { "path": string, "contents": string }
+//!   ]
+//! }
+//! ```
+//!
+//! Paths must be relative. Files are written to the output directory.
+//! Nonzero exit code of the generator aborts code generation.
+
+use std::{
+    fs,
+    io::Write,
+    path::{Path, Component},
+    process::{Command, Stdio},
+    thread,
+};
+
+use serde::Deserialize;
+
+use crate::logic::{
+    ir::IrFile,
+    validation::ParsedFile,
+};
+
+#[derive(Debug, Deserialize)]
+struct GeneratorOutput {
+    files: Vec<GeneratedFile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeneratedFile {
+    path: String,
+    contents: String,
+}
+
+pub fn parsed_file_to_external(parsed_file: &ParsedFile, command: &str, output_dir: &str) {
+    let ir = serde_json::to_vec(&IrFile::new(parsed_file)).unwrap();
+
+    let generator_output = match run_generator(command, ir) {
+        Ok(generator_output) => generator_output,
+        Err(e) => {
+            println!("error: external generator '{}' failed: {}", command, e);
+            std::process::exit(-1)
+        }
+    };
+
+    for file in &generator_output.files {
+        if let Err(e) = write_generated_file(output_dir, file) {
+            println!("error: writing file '{}' from external generator '{}' failed: {}", file.path, command, e);
+            std::process::exit(-1)
+        }
+    }
+}
+
+fn run_generator(command: &str, ir: Vec<u8>) -> Result<GeneratorOutput, String> {
+    let mut child = Command::new(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    // Write from another thread so that the generator can't block
+    // by filling the stdout pipe before it has read all input.
+    let mut stdin = child.stdin.take().unwrap();
+    let writer = thread::spawn(move || stdin.write_all(&ir));
+
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    let write_result = writer.join().unwrap();
+
+    if !output.status.success() {
+        return Err(format!("exit code: {:?}", output.status.code()));
+    }
+
+    write_result.map_err(|e| format!("writing to stdin failed: {}", e))?;
+
+    serde_json::from_slice(&output.stdout).map_err(|e| format!("invalid output: {}", e))
+}
+
+fn write_generated_file(output_dir: &str, file: &GeneratedFile) -> Result<(), String> {
+    let path = Path::new(&file.path);
+    let only_normal_components = path.components().all(|c| matches!(c, Component::Normal(_)));
+
+    if path.as_os_str().is_empty() || !only_normal_components {
+        return Err("path must be relative file path without '..' components".to_string());
+    }
+
+    let path = Path::new(output_dir).join(path);
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    fs::write(&path, &file.contents).map_err(|e| e.to_string())
+}
@@ -0,0 +1,168 @@
+//! Linux kernel style header code generation.
+//!
+//! Fields are defined as masks (`GENMASK` or `BIT`) which are meant to be
+//! used with `FIELD_GET` and `FIELD_PREP` from `<linux/bitfield.h>`.
+//! Reserved fields are not included.
+
+use std::{
+    fmt::Write as FmtWrite,
+    io::Write,
+    fs,
+};
+
+use inflections::Inflect;
+
+use crate::logic::validation::{
+    ParsedFile,
+    register::{
+        Register,
+        RegisterFunction,
+        RegisterEnum,
+        RegisterLocation,
+        RegisterSize,
+    },
+};
+
+use super::c::{
+    register_prefix,
+    c_comment,
+};
+
+pub fn parsed_file_to_linux(parsed_file: &ParsedFile, output: &str) {
+    let header = parsed_file_to_linux_header(parsed_file);
+
+    let mut f = fs::File::create(output).unwrap();
+    f.write_all(header.as_bytes()).unwrap();
+}
+
+fn parsed_file_to_linux_header(parsed_file: &ParsedFile) -> String {
+    let rd = &parsed_file.description;
+    let mut output = String::new();
+
+    let include_guard = format!("__{}_H__", rd.name.as_str().to_constant_case());
+
+    writeln!(output, "/* Generated from register description `{}` */", c_comment(rd.name.as_str())).unwrap();
+    if let Some(description) = &rd.description {
+        writeln!(output, "/* {} */", c_comment(description)).unwrap();
+    }
+    writeln!(output).unwrap();
+    writeln!(output, "#ifndef {}", include_guard).unwrap();
+    writeln!(output, "#define {}", include_guard).unwrap();
+    writeln!(output).unwrap();
+    writeln!(output, "#include <linux/bits.h>").unwrap();
+
+    for (group, registers) in parsed_file.register_groups() {
+        for r in registers {
+            register_to_linux(&mut output, group, r);
+        }
+    }
+
+    writeln!(output).unwrap();
+    writeln!(output, "#endif /* {} */", include_guard).unwrap();
+
+    output
+}
+
+fn register_to_linux(output: &mut String, group: Option<&str>, r: &Register) {
+    let prefix = register_prefix(group, r);
+
+    writeln!(output).unwrap();
+    match &r.description {
+        Some(description) => writeln!(output, "/* {} ({}) - {} */", c_comment(r.name.as_str()), r.access_mode, c_comment(description)).unwrap(),
+        None => writeln!(output, "/* {} ({}) */", c_comment(r.name.as_str()), r.access_mode).unwrap(),
+    }
+
+    writeln!(output, "#define {}_REG\t{}", prefix, location_value(r.read_location)).unwrap();
+    if r.read_location != r.write_location {
+        writeln!(output, "#define {}_WR_REG\t{}", prefix, location_value(r.write_location)).unwrap();
+    }
+
+    for f in r.functions.iter().filter(|f| f.status.is_normal()) {
+        let field_name = field_name(&prefix, f);
+
+        if let Some(description) = f.description() {
+            writeln!(output, "/* {} */", c_comment(description)).unwrap();
+        }
+
+        writeln!(output, "#define {}\t{}", field_name, field_mask(f, r.size_in_bits)).unwrap();
+
+        if let Some(e) = r.enums.iter().find(|e| e.range == f.range) {
+            enum_to_linux(output, &field_name, e);
+        }
+    }
+}
+
+/// Enum values are field values, so they should be used with `FIELD_PREP`
+/// and compared to `FIELD_GET` results.
+fn enum_to_linux(output: &mut String, field_name: &str, e: &RegisterEnum) {
+    let field_name = field_name.strip_suffix("_MASK").unwrap_or(field_name);
+
+    for v in &e.values {
+        let name = format!("{}_{}", field_name, v.name.as_str().to_constant_case());
+
+        match &v.description {
+            Some(description) => writeln!(output, "#define  {}\t{} /* {} */", name, integer(v.value), c_comment(description)).unwrap(),
+            None => writeln!(output, "#define  {}\t{}", name, integer(v.value)).unwrap(),
+        }
+    }
+}
+
+/// Single bit fields are named like the field. Other fields have
+/// postfix `_MASK`.
+fn field_name(register_prefix: &str, f: &RegisterFunction) -> String {
+    let name = format!("{}_{}", register_prefix, f.name().unwrap().to_constant_case());
+
+    if f.range.bit_count().get() == 1 {
+        name
+    } else {
+        format!("{}_MASK", name)
+    }
+}
+
+/// Masks of 64-bit registers use the `_ULL` variants of the macros.
+fn field_mask(f: &RegisterFunction, size: RegisterSize) -> String {
+    let postfix = if size == RegisterSize::Size64 { "_ULL" } else { "" };
+
+    if f.range.bit_count().get() == 1 {
+        format!("BIT{}({})", postfix, f.range.lsb)
+    } else {
+        format!("GENMASK{}({}, {})", postfix, f.range.msb, f.range.lsb)
+    }
+}
+
+fn location_value(location: RegisterLocation) -> String {
+    match location {
+        RegisterLocation::Index(value) |
+        RegisterLocation::Absolute(value) |
+        RegisterLocation::Relative(value) => format!("{:#x}{}", value, integer_suffix(value)),
+    }
+}
+
+fn integer(value: u64) -> String {
+    format!("{}{}", value, integer_suffix(value))
+}
+
+fn integer_suffix(value: u64) -> &'static str {
+    if value > u64::from(u32::MAX) {
+        "ULL"
+    } else {
+        ""
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::test_data;
+
+    #[test]
+    fn header() {
+        let header = parsed_file_to_linux_header(&test_data::parsed_file(test_data::DEVICE));
+
+        assert!(header.contains("#define TIMER_CONTROL_REG\t0x40000000\n"));
+        assert!(header.contains("#define TIMER_CONTROL_PRESCALER_MASK\tGENMASK(11, 8)\n"));
+        assert!(header.contains("#define  TIMER_CONTROL_MODE_PERIODIC\t1\n"));
+        assert!(header.contains("#define UART_CONFIG_PARITY\tBIT(0)\n"));
+        assert!(header.ends_with("#endif /* __DEVICE_H__ */\n"));
+    }
+}
@@ -0,0 +1,362 @@
+//! Python module code generation.
+//!
+//! Every register has a class with a `Field` descriptor for every
+//! non-reserved bit field. Class names are prefixed with the register
+//! group name, so registers with the same name in different groups
+//! don't collide.
+
+use std::{
+    fmt::Write as FmtWrite,
+    io::Write,
+    fs,
+};
+
+use inflections::Inflect;
+
+use crate::logic::validation::{
+    ParsedFile,
+    register::{
+        Register,
+        RegisterFunction,
+        RegisterEnum,
+        RegisterLocation,
+        AccessMode,
+    },
+};
+
+const PYTHON_KEYWORDS: &[&str] = &[
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break",
+    "class", "continue", "def", "del", "elif", "else", "except", "finally",
+    "for", "from", "global", "if", "import", "in", "is", "lambda", "nonlocal",
+    "not", "or", "pass", "raise", "return", "try", "while", "with", "yield",
+];
+
+/// Attributes of `Register` and register class constants in snake case.
+/// Fields with these names would be confused with them or shadow them.
+const REGISTER_ATTRIBUTES: &[&str] = &[
+    "decode", "encode", "name", "group", "size", "access", "read_location",
+    "write_location", "fields",
+];
+
+/// Module level names which register class bodies use. Enum classes
+/// with these names would shadow them.
+const MODULE_NAMES: &[&str] = &["Field", "IntEnum", "Location", "Register"];
+
+const COMMON_DEFINITIONS: &str = r#"
+Location = namedtuple("Location", ["kind", "value"])
+Location.__doc__ = """Register location. Kind is "index", "absolute" or "relative"."""
+
+
+class Field:
+    """Register bit field
+
+    Reading the attribute from a register instance returns the field value.
+    Values of fields with an enum are enum members if the value is defined
+    in the enum. Values of other single bit fields are booleans.
+    """
+
+    def __init__(self, name, msb, lsb, enum=None):
+        self.name = name
+        self.msb = msb
+        self.lsb = lsb
+        self.enum = enum
+        self.mask = (1 << (msb - lsb + 1)) - 1
+
+    def decode(self, register_value):
+        value = (register_value >> self.lsb) & self.mask
+        if self.enum is not None:
+            try:
+                return self.enum(value)
+            except ValueError:
+                return value
+        if self.msb == self.lsb:
+            return bool(value)
+        return value
+
+    def encode(self, register_value, value):
+        value = int(value)
+        if value < 0 or value > self.mask:
+            raise ValueError("value {} does not fit to field '{}'".format(value, self.name))
+        return (register_value & ~(self.mask << self.lsb)) | (value << self.lsb)
+
+    def __get__(self, register, owner=None):
+        if register is None:
+            return self
+        return self.decode(register._raw)
+
+    def __set__(self, register, value):
+        register._raw = self.encode(register._raw, value)
+
+
+class Register:
+    """Register value
+
+    `int(register)` returns the raw register value.
+    """
+
+    FIELDS = ()
+
+    def __init__(self, raw=0):
+        self._raw = int(raw)
+
+    def __int__(self):
+        return self._raw
+
+    def __index__(self):
+        return self._raw
+
+    def __eq__(self, other):
+        return type(self) is type(other) and self._raw == other._raw
+
+    def __repr__(self):
+        fields = "".join(", {}={!r}".format(f.name, f.decode(self._raw)) for f in self.FIELDS)
+        return "{}({:#x}{})".format(type(self).__name__, self._raw, fields)
+
+    @classmethod
+    def decode(cls, value):
+        """Field values of register value `value`. Reserved fields are not included."""
+        return {f.name: f.decode(value) for f in cls.FIELDS}
+
+    @classmethod
+    def encode(cls, **fields):
+        """Register value from field values. Missing fields are zero."""
+        value = 0
+        for name, field_value in fields.items():
+            field = next((f for f in cls.FIELDS if f.name == name), None)
+            if field is None:
+                raise TypeError("register {} does not have field '{}'".format(cls.__name__, name))
+            value = field.encode(value, field_value)
+        return value
+"#;
+
+const LOCATION_FUNCTIONS: &str = r#"
+
+def find_registers(location, access="r"):
+    """Register classes which have location `location` for access `access` ("r" or "w")"""
+    return [register for register, a, l in LOCATIONS if a == access and l == location]
+"#;
+
+pub fn parsed_file_to_python(parsed_file: &ParsedFile, output: &str) {
+    let errors = check_enum_names(parsed_file);
+    if !errors.is_empty() {
+        for e in errors {
+            println!("error: {}", e);
+        }
+        println!("error: Python code generation failed");
+        std::process::exit(-1)
+    }
+
+    let module = parsed_file_to_python_module(parsed_file);
+
+    let mut f = fs::File::create(output).unwrap();
+    f.write_all(module.as_bytes()).unwrap();
+}
+
+/// Enum classes are named after the enums and they are nested in the
+/// register class, so enum names must be unique in the register.
+fn check_enum_names(parsed_file: &ParsedFile) -> Vec<String> {
+    let mut errors = vec![];
+
+    for r in parsed_file.all_registers() {
+        let mut enum_classes: Vec<String> = vec![];
+
+        for e in &r.enums {
+            let enum_class = enum_class_name(e);
+            if MODULE_NAMES.contains(&enum_class.as_str()) {
+                errors.push(format!("enum '{}' of register '{}' has reserved Python name '{}'", e.name, r.name, enum_class));
+            } else if enum_classes.contains(&enum_class) {
+                errors.push(format!("register '{}' has multiple enums with Python name '{}'", r.name, enum_class));
+            }
+            enum_classes.push(enum_class);
+        }
+    }
+
+    errors
+}
+
+fn parsed_file_to_python_module(parsed_file: &ParsedFile) -> String {
+    let rd = &parsed_file.description;
+    let mut output = String::new();
+
+    writeln!(output, "\"\"\"Generated from register description `{}`", docstring(rd.name.as_str())).unwrap();
+    if let Some(description) = &rd.description {
+        writeln!(output).unwrap();
+        writeln!(output, "{}", docstring(description)).unwrap();
+    }
+    writeln!(output, "\"\"\"").unwrap();
+    writeln!(output).unwrap();
+    writeln!(output, "from collections import namedtuple").unwrap();
+    writeln!(output, "from enum import IntEnum").unwrap();
+    writeln!(output).unwrap();
+    output.push_str(COMMON_DEFINITIONS);
+
+    let mut locations = vec![];
+
+    for (group, registers) in parsed_file.register_groups() {
+        for r in registers {
+            let class_name = class_name(group, r);
+            register_to_python(&mut output, &class_name, group, r);
+
+            if let AccessMode::Read | AccessMode::ReadWrite = r.access_mode {
+                locations.push(format!("({}, \"r\", {})", class_name, location(r.read_location)));
+            }
+            if let AccessMode::Write | AccessMode::ReadWrite = r.access_mode {
+                locations.push(format!("({}, \"w\", {})", class_name, location(r.write_location)));
+            }
+        }
+    }
+
+    writeln!(output).unwrap();
+    writeln!(output).unwrap();
+    writeln!(output, "# Register location table. Items are (register class, access, location)").unwrap();
+    writeln!(output, "# and access is \"r\" or \"w\".").unwrap();
+    writeln!(output, "LOCATIONS = (").unwrap();
+    for location in locations {
+        writeln!(output, "    {},", location).unwrap();
+    }
+    writeln!(output, ")").unwrap();
+    output.push_str(LOCATION_FUNCTIONS);
+
+    output
+}
+
+fn register_to_python(output: &mut String, class_name: &str, group: Option<&str>, r: &Register) {
+    let fields: Vec<&RegisterFunction> = r.functions.iter().filter(|f| f.status.is_normal()).collect();
+
+    writeln!(output).unwrap();
+    writeln!(output).unwrap();
+    writeln!(output, "class {}(Register):", class_name).unwrap();
+    match &r.description {
+        Some(description) => writeln!(output, "    \"\"\"{} ({}) - {}\"\"\"", docstring(r.name.as_str()), r.access_mode, docstring(description)).unwrap(),
+        None => writeln!(output, "    \"\"\"{} ({})\"\"\"", docstring(r.name.as_str()), r.access_mode).unwrap(),
+    }
+    writeln!(output).unwrap();
+    writeln!(output, "    NAME = {:?}", r.name.as_str()).unwrap();
+    writeln!(output, "    GROUP = {:?}", group.unwrap_or("")).unwrap();
+    writeln!(output, "    SIZE = {}", r.size_in_bits).unwrap();
+    writeln!(output, "    ACCESS = \"{}\"", r.access_mode).unwrap();
+    writeln!(output, "    READ_LOCATION = {}", location(r.read_location)).unwrap();
+    writeln!(output, "    WRITE_LOCATION = {}", location(r.write_location)).unwrap();
+
+    for f in &fields {
+        if let Some(e) = r.enums.iter().find(|e| e.range == f.range) {
+            enum_to_python(output, e);
+        }
+    }
+
+    writeln!(output).unwrap();
+    for f in &fields {
+        let enum_type = match r.enums.iter().find(|e| e.range == f.range) {
+            Some(e) => format!(", {}", enum_class_name(e)),
+            None => String::new(),
+        };
+
+        if let Some(description) = f.description() {
+            writeln!(output, "    # {}", comment(description)).unwrap();
+        }
+
+        let name = field_name(f);
+        writeln!(output, "    {} = Field({:?}, {}, {}{})", name, name, f.range.msb, f.range.lsb, enum_type).unwrap();
+    }
+
+    let field_names: Vec<String> = fields.iter().map(|f| field_name(f)).collect();
+    writeln!(output).unwrap();
+    match field_names.len() {
+        0 => writeln!(output, "    FIELDS = ()").unwrap(),
+        1 => writeln!(output, "    FIELDS = ({},)", field_names[0]).unwrap(),
+        _ => writeln!(output, "    FIELDS = ({})", field_names.join(", ")).unwrap(),
+    }
+}
+
+fn enum_to_python(output: &mut String, e: &RegisterEnum) {
+    writeln!(output).unwrap();
+    writeln!(output, "    class {}(IntEnum):", enum_class_name(e)).unwrap();
+    if let Some(description) = &e.description {
+        writeln!(output, "        \"\"\"{}\"\"\"", docstring(description)).unwrap();
+        writeln!(output).unwrap();
+    }
+
+    for v in &e.values {
+        match &v.description {
+            Some(description) => writeln!(output, "        {} = {:#x}  # {}", v.name.as_str().to_constant_case(), v.value, comment(description)).unwrap(),
+            None => writeln!(output, "        {} = {:#x}", v.name.as_str().to_constant_case(), v.value).unwrap(),
+        }
+    }
+}
+
+/// Pascal case register name prefixed with the group name.
+fn class_name(group: Option<&str>, r: &Register) -> String {
+    match group {
+        Some(group) => format!("{}{}", group.to_pascal_case(), r.name.as_str().to_pascal_case()),
+        None => r.name.as_str().to_pascal_case(),
+    }
+}
+
+fn enum_class_name(e: &RegisterEnum) -> String {
+    e.name.as_str().to_pascal_case()
+}
+
+/// Snake case field name. Python keywords and `Register` attribute
+/// names have postfix `_`.
+fn field_name(f: &RegisterFunction) -> String {
+    let name = f.name().unwrap().to_snake_case();
+
+    if PYTHON_KEYWORDS.contains(&name.as_str()) || REGISTER_ATTRIBUTES.contains(&name.as_str()) {
+        format!("{}_", name)
+    } else {
+        name
+    }
+}
+
+fn location(location: RegisterLocation) -> String {
+    match location {
+        RegisterLocation::Index(value) => format!("Location(\"index\", {:#x})", value),
+        RegisterLocation::Absolute(value) => format!("Location(\"absolute\", {:#x})", value),
+        RegisterLocation::Relative(value) => format!("Location(\"relative\", {:#x})", value),
+    }
+}
+
+fn docstring(text: &str) -> String {
+    text.replace('\\', "\\\\").replace("\"\"\"", "\\\"\\\"\\\"")
+}
+
+fn comment(text: &str) -> String {
+    text.replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::test_data;
+
+    #[test]
+    fn module() {
+        let module = parsed_file_to_python_module(&test_data::parsed_file(test_data::DEVICE));
+
+        assert!(module.contains("class TimerControl(Register):\n"));
+        assert!(module.contains("    class Mode(IntEnum):\n"));
+        assert!(module.contains("        ONE_SHOT = 0x0  # Stop after one period\n"));
+        assert!(module.contains("    mode = Field(\"mode\", 7, 4, Mode)\n"));
+        assert!(module.contains("    FIELDS = (prescaler, mode, enable)\n"));
+        assert!(module.contains("    (UartData, \"w\", Location(\"absolute\", 0x40001000)),\n"));
+    }
+
+    #[test]
+    fn register_attribute_field_name_has_postfix() {
+        let text = test_data::DEVICE.replace("name = \"Parity\"", "name = \"Size\"");
+        let module = parsed_file_to_python_module(&test_data::parsed_file(&text));
+
+        assert!(module.contains("    SIZE = 8\n"));
+        assert!(module.contains("    size_ = Field(\"size_\", 0, 0)\n"));
+    }
+
+    #[test]
+    fn duplicate_enum_class_is_error() {
+        let text = test_data::DEVICE.replace(
+            "[[register.timer]]\nname = \"Count\"",
+            "[[register.timer.enum]]\nname = \"Mode\"\nbit = \"11:8\"\nvalues = [ { value = 0, name = \"Off\" } ]\n\n[[register.timer]]\nname = \"Count\"",
+        );
+        let errors = check_enum_names(&test_data::parsed_file(&text));
+        assert_eq!(errors, vec!["register 'Control' has multiple enums with Python name 'Mode'".to_string()]);
+    }
+}
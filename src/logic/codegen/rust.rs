@@ -1,5 +1,9 @@
 pub mod register;
 pub mod register_trait;
+pub mod mmio;
+pub mod port_io;
+pub mod mock_io;
+pub mod traced;
 
 
 use std::{
@@ -15,11 +19,13 @@ use syn::{Ident, LitInt, IntSuffix};
 use proc_macro2::{Span, TokenStream};
 use inflections::Inflect;
 
-use crate::logic::{
-    validation::{
+use crate::{
+    config::RustOptions,
+    logic::validation::{
         ParsedFile,
         Registers,
         register::{
+            Register,
             RegisterSize,
         },
         register_description::{
@@ -28,14 +34,25 @@ use crate::logic::{
     },
 };
 
-pub fn parsed_file_to_rust(parsed_file: &ParsedFile, output: &str) {
-    let token_stream = convert_parsed_file_to_token_stream(parsed_file);
+pub fn parsed_file_to_rust(parsed_file: &ParsedFile, output: &str, options: &RustOptions) {
+    let errors = check_rust_generation(parsed_file, options);
+    if !errors.is_empty() {
+        for e in errors {
+            println!("error: {}", e);
+        }
+        println!("error: Rust code generation failed");
+        std::process::exit(-1)
+    }
+
+    let token_stream = convert_parsed_file_to_token_stream(parsed_file, options);
 
     let mut f = fs::File::create(output).unwrap();
     f.write_all(token_stream.to_string().as_bytes()).unwrap();
     drop(f);
 
+    // Async functions require Rust 2018.
     let rustfmt_result = Command::new("rustfmt")
+        .args(["--edition", "2018"])
         .arg(output)
         .status();
 
@@ -50,9 +67,98 @@ pub fn parsed_file_to_rust(parsed_file: &ParsedFile, output: &str) {
 
 }
 
-fn convert_parsed_file_to_token_stream(parsed_file: &ParsedFile) -> TokenStream {
-    let trait_module = register_trait::register_trait_module(&parsed_file.description);
+/// Errors for register descriptions which would generate invalid code
+/// with the selected options.
+fn check_rust_generation(parsed_file: &ParsedFile, options: &RustOptions) -> Vec<String> {
+    let mut errors = vec![];
+
+    if let Some(Registers::Groups(groups)) = &parsed_file.registers {
+        let helper_modules = helper_module_names(options);
+        for (name, _) in groups {
+            let module_name = name.to_snake_case();
+            if helper_modules.contains(&module_name.as_str()) {
+                errors.push(format!(
+                    "module of register group '{}' has the same name as generated module '{}', rename the register group",
+                    name,
+                    module_name,
+                ));
+            }
+        }
+    }
+
+    if options.port_io {
+        errors.extend(port_io::check_port_io(parsed_file));
+    }
+
+    errors
+}
+
+/// Names of the top level modules which are generated in addition
+/// to register group modules.
+fn helper_module_names(options: &RustOptions) -> Vec<&'static str> {
+    let mut names = vec!["register_trait"];
+
+    if options.async_io {
+        names.push("register_trait_async");
+    }
+    if options.mock_io || options.traced {
+        names.push("register_access");
+    }
+    if options.mmio {
+        names.push("mmio");
+    }
+    if options.port_io {
+        names.push("port_io");
+    }
+    if options.mock_io {
+        names.push("mock_io");
+    }
+    if options.traced {
+        names.push("traced");
+    }
 
+    names
+}
+
+fn convert_parsed_file_to_token_stream(parsed_file: &ParsedFile, options: &RustOptions) -> TokenStream {
+    let trait_module = register_trait::register_trait_module(&parsed_file.description, options);
+
+    let async_trait_module = if options.async_io {
+        register_trait::register_trait_async_module(&parsed_file.description)
+    } else {
+        quote! {}
+    };
+
+    let mmio_module = if options.mmio {
+        mmio::mmio_module(parsed_file)
+    } else {
+        quote! {}
+    };
+
+    let port_io_module = if options.port_io {
+        port_io::port_io_module(parsed_file)
+    } else {
+        quote! {}
+    };
+
+
+    let register_access_module = if options.mock_io || options.traced {
+        register_trait::register_access_module()
+    } else {
+        quote! {}
+    };
+
+    let mock_io_module = if options.mock_io {
+        mock_io::mock_io_module(&parsed_file.description, options)
+    } else {
+        quote! {}
+    };
+
+    let traced_module = if options.traced {
+        traced::traced_module(&parsed_file.description, options)
+    } else {
+        quote! {}
+    };
 
     let groups: Vec<TokenStream> = match &parsed_file.registers {
         None => vec![],
@@ -61,29 +167,13 @@ fn convert_parsed_file_to_token_stream(parsed_file: &ParsedFile) -> TokenStream
                 let module_name = ident(name.to_snake_case());
                 let group_str = name.to_pascal_case();
                 let group_type = ident(format!("{}Group", group_str));
-                let registers_modules = register::registers_to_module(&registers, &parsed_file.description, &group_type);
-                let register_group = register::register_group(&registers, &group_type, &group_str);
-                quote! {
-                    pub mod #module_name {
-                        use super::register_trait::*;
-                        #register_group
-                        #registers_modules
-                    }
-                }
+                group_module(parsed_file, options, registers, &module_name, &group_type, &group_str, name)
             }).collect()
         }
         Some(Registers::OnlyRegisters(registers)) => {
             let group_type = ident("RegisterGroup");
-            let registers_modules = register::registers_to_module(&registers, &parsed_file.description, &group_type);
-            let register_group = register::register_group(&registers, &group_type, "");
             vec![
-                quote! {
-                    pub mod register {
-                        use super::register_trait::*;
-                        #register_group
-                        #registers_modules
-                    }
-                }
+                group_module(parsed_file, options, registers, &ident("register"), &group_type, "", "")
             ]
         }
     };
@@ -103,11 +193,59 @@ fn convert_parsed_file_to_token_stream(parsed_file: &ParsedFile) -> TokenStream
         #additional_doc
 
         #trait_module
+        #async_trait_module
+        #register_access_module
+        #mmio_module
+        #port_io_module
+        #mock_io_module
+        #traced_module
 
         #( #groups )*
     }
 }
 
+fn group_module(
+    parsed_file: &ParsedFile,
+    options: &RustOptions,
+    registers: &[Register],
+    module_name: &Ident,
+    group_type: &Ident,
+    group_str: &str,
+    group_name: &str,
+) -> TokenStream {
+    let registers_modules = register::registers_to_module(registers, &parsed_file.description, group_type, options);
+    let register_group = register::register_group(registers, group_type, group_str, options);
+
+    let mock_io = if options.mock_io {
+        mock_io::mock_group_impl(registers, group_type)
+    } else {
+        quote! {}
+    };
+
+    let traced = if options.traced {
+        traced::traced_group_impl(registers, group_type, group_name)
+    } else {
+        quote! {}
+    };
+
+    let async_traits = if options.async_io {
+        quote! { use super::register_trait_async::*; }
+    } else {
+        quote! {}
+    };
+
+    quote! {
+        pub mod #module_name {
+            use super::register_trait::*;
+            #async_traits
+            #register_group
+            #mock_io
+            #traced
+            #registers_modules
+        }
+    }
+}
+
 pub fn ident<T: AsRef<str>>(text: T) -> Ident {
     Ident::new(text.as_ref(), Span::call_site())
 }
@@ -139,3 +277,144 @@ impl AddressSize {
         ident(type_str)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::test_data;
+
+    /// Compiles the generated code as module `generated` of a test crate
+    /// which contains `tests` and runs the tests. Returns compiler or
+    /// test output if compiling or testing fails.
+    fn compile_and_test(name: &str, options: &RustOptions, tests: &str) -> Result<(), String> {
+        let parsed_file = test_data::parsed_file(test_data::DEVICE);
+        assert_eq!(check_rust_generation(&parsed_file, options), Vec::<String>::new());
+        let code = convert_parsed_file_to_token_stream(&parsed_file, options).to_string();
+
+        let dir = std::env::temp_dir().join(format!("register-description-tools-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("generated.rs"), code).unwrap();
+        fs::write(dir.join("lib.rs"), format!("#[allow(dead_code, unused_imports)]\nmod generated;\n{}", tests)).unwrap();
+
+        let compiler = Command::new("rustc")
+            .args(["--edition", "2021", "--test", "-C", "debug-assertions=on", "-o"])
+            .arg(dir.join("tests"))
+            .arg(dir.join("lib.rs"))
+            .output()
+            .unwrap();
+
+        let result = if !compiler.status.success() {
+            Err(String::from_utf8_lossy(&compiler.stderr).into_owned())
+        } else {
+            let tests = Command::new(dir.join("tests")).output().unwrap();
+            if tests.status.success() {
+                Ok(())
+            } else {
+                Err(String::from_utf8_lossy(&tests.stdout).into_owned())
+            }
+        };
+
+        fs::remove_dir_all(&dir).unwrap();
+        result
+    }
+
+    #[test]
+    fn mock_io() {
+        let options = RustOptions { mock_io: true, ..RustOptions::default() };
+        let tests = r#"
+use generated::uart::{UartRegisters, MockIo};
+use generated::mock_io::{Location, Op};
+
+#[test]
+fn expect_write() {
+    let mut io = MockIo::new();
+    io.set(Location::Absolute(0x40001004), 0x1);
+    let mut uart = UartRegisters::new(io);
+    assert!(uart.config().read().parity().bit());
+    uart.data().write(|w| w.byte().bits(0x15));
+    uart.config().modify(|_, w| w.parity().clear_bit());
+    let io = uart.into_io();
+    io.expect_write("Data", 0x15).before_write("Config");
+    assert_eq!(io.written("Config"), vec![0]);
+    assert_eq!(io.log()[0].op, Op::Read);
+    assert_eq!(io.log()[0].register, Some("Config"));
+}
+
+#[test]
+fn before_write_finds_later_write() {
+    let mut uart = UartRegisters::new(MockIo::new());
+    uart.config().modify(|_, w| w.parity().set_bit());
+    uart.data().write(|w| w.byte().bits(0x15));
+    uart.config().modify(|_, w| w.parity().clear_bit());
+    uart.into_io().expect_write("Data", 0x15).before_write("Config");
+}
+
+#[test]
+#[should_panic]
+fn before_write_fails_without_later_write() {
+    let mut uart = UartRegisters::new(MockIo::new());
+    uart.config().modify(|_, w| w.parity().set_bit());
+    uart.data().write(|w| w.byte().bits(0x15));
+    uart.into_io().expect_write("Data", 0x15).before_write("Config");
+}
+"#;
+        compile_and_test("mock-io", &options, tests).unwrap();
+    }
+
+    #[test]
+    fn field_overflow_checks() {
+        let options = RustOptions { mock_io: true, debug_assert_bits: true, ..RustOptions::default() };
+        let tests = r#"
+use generated::uart::{UartRegisters, MockIo};
+
+#[test]
+fn try_bits() {
+    let mut uart = UartRegisters::new(MockIo::new());
+    let mut result = None;
+    uart.data().write(|w| { result = Some(w.byte().try_bits(0x40).map(|_| ())); w });
+    assert_eq!(result.unwrap().unwrap_err().bit_count, 6);
+    uart.data().write(|w| w.byte().try_bits(0x3f).unwrap());
+    assert_eq!(uart.into_io().written("Data"), vec![0, 0x3f]);
+}
+
+#[test]
+#[should_panic(expected = "does not fit")]
+fn debug_assert_bits() {
+    let mut uart = UartRegisters::new(MockIo::new());
+    uart.data().write(|w| w.byte().bits(0x40));
+}
+"#;
+        compile_and_test("overflow", &options, tests).unwrap();
+    }
+
+    #[test]
+    fn const_builder() {
+        let options = RustOptions { mock_io: true, const_fn: true, ..RustOptions::default() };
+        let tests = r#"
+use generated::uart::{UartRegisters, MockIo, data};
+
+const DATA: data::W = data::W::new().with_channel(2).with_byte(0x15);
+
+#[test]
+fn write_value() {
+    let mut uart = UartRegisters::new(MockIo::new());
+    uart.data().write_value(DATA);
+    assert_eq!(uart.into_io().written("Data"), vec![0x95]);
+}
+"#;
+        compile_and_test("const-builder", &options, tests).unwrap();
+
+        let overflow = "const DATA: generated::uart::data::W = generated::uart::data::W::new().with_byte(0x40);\n";
+        let errors = compile_and_test("const-builder-overflow", &options, overflow).unwrap_err();
+        assert!(errors.contains("value does not fit to 6 bit field `byte`"), "{}", errors);
+    }
+
+    #[test]
+    fn group_module_name_collision_is_error() {
+        let options = RustOptions { mock_io: true, ..RustOptions::default() };
+        let parsed_file = test_data::parsed_file(&test_data::DEVICE.replace("register.uart", "register.mock_io"));
+        assert_eq!(check_rust_generation(&parsed_file, &options), vec![
+            "module of register group 'mock_io' has the same name as generated module 'mock_io', rename the register group".to_string(),
+        ]);
+    }
+}
@@ -0,0 +1,133 @@
+
+use std::collections::BTreeSet;
+
+use quote::quote;
+
+use proc_macro2::TokenStream;
+
+use crate::logic::validation::{
+    ParsedFile,
+    register::{
+        RegisterLocation,
+        RegisterSize,
+    },
+};
+
+use super::ident;
+
+/// Register sizes used with absolute and relative register locations.
+fn mmio_register_sizes(parsed_file: &ParsedFile) -> (BTreeSet<RegisterSize>, BTreeSet<RegisterSize>) {
+    let mut absolute = BTreeSet::new();
+    let mut relative = BTreeSet::new();
+
+    for r in parsed_file.all_registers() {
+        for location in &[r.read_location, r.write_location] {
+            match location {
+                RegisterLocation::Absolute(_) => absolute.insert(r.size_in_bits),
+                RegisterLocation::Relative(_) => relative.insert(r.size_in_bits),
+                RegisterLocation::Index(_) => false,
+            };
+        }
+    }
+
+    (absolute, relative)
+}
+
+pub fn mmio_module(parsed_file: &ParsedFile) -> TokenStream {
+    let address_type = parsed_file.description.address_size.rust_type();
+    let (absolute_sizes, relative_sizes) = mmio_register_sizes(parsed_file);
+
+    let mut items = vec![];
+
+    if !absolute_sizes.is_empty() {
+        let io_impls = absolute_sizes.iter().map(|size| {
+            let size = ident(size.rust_unsigned_integer());
+            quote! {
+                impl <G: RegisterGroup> RegisterAbsIoR<G, #size> for Mmio {
+                    #[inline]
+                    fn read(&mut self, abs_address: #address_type) -> #size {
+                        unsafe { core::ptr::read_volatile(abs_address as usize as *const #size) }
+                    }
+                }
+
+                impl <G: RegisterGroup> RegisterAbsIoW<G, #size> for Mmio {
+                    #[inline]
+                    fn write(&mut self, abs_address: #address_type, value: #size) {
+                        unsafe { core::ptr::write_volatile(abs_address as usize as *mut #size, value) }
+                    }
+                }
+            }
+        });
+
+        items.push(quote! {
+            #[doc = "Memory mapped IO for registers with absolute address"]
+            pub struct Mmio {
+                _private: (),
+            }
+
+            impl Mmio {
+                #[doc = "# Safety"]
+                #[doc = ""]
+                #[doc = "Register addresses must be valid for volatile reads and writes."]
+                pub unsafe fn new() -> Self {
+                    Self { _private: () }
+                }
+            }
+
+            #( #io_impls )*
+        });
+    }
+
+    if !relative_sizes.is_empty() {
+        let io_impls = relative_sizes.iter().map(|size| {
+            let size = ident(size.rust_unsigned_integer());
+            quote! {
+                impl <G: RegisterGroup> RegisterRelIoR<G, #size> for MmioRel {
+                    #[inline]
+                    fn read(&mut self, rel_address: #address_type) -> #size {
+                        let address = self.base + rel_address as usize;
+                        unsafe { core::ptr::read_volatile(address as *const #size) }
+                    }
+                }
+
+                impl <G: RegisterGroup> RegisterRelIoW<G, #size> for MmioRel {
+                    #[inline]
+                    fn write(&mut self, rel_address: #address_type, value: #size) {
+                        let address = self.base + rel_address as usize;
+                        unsafe { core::ptr::write_volatile(address as *mut #size, value) }
+                    }
+                }
+            }
+        });
+
+        items.push(quote! {
+            #[doc = "Memory mapped IO for registers with address relative to runtime base address"]
+            pub struct MmioRel {
+                base: usize,
+            }
+
+            impl MmioRel {
+                #[doc = "# Safety"]
+                #[doc = ""]
+                #[doc = "Register addresses relative to `base` must be valid for volatile reads and writes."]
+                pub unsafe fn new(base: usize) -> Self {
+                    Self { base }
+                }
+
+                pub fn base(&self) -> usize {
+                    self.base
+                }
+            }
+
+            #( #io_impls )*
+        });
+    }
+
+    quote! {
+        pub mod mmio {
+            use super::register_trait::*;
+
+            #( #items )*
+        }
+    }
+}
@@ -0,0 +1,269 @@
+
+use quote::quote;
+
+use syn::Ident;
+use proc_macro2::TokenStream;
+
+use crate::logic::validation::{
+    register::{
+        Register,
+        AccessMode,
+    },
+    register_description::RegisterDescription,
+};
+
+use super::{
+    RustOptions,
+    ident,
+    register_trait::access_location,
+};
+
+pub fn mock_io_module(rd: &RegisterDescription, options: &RustOptions) -> TokenStream {
+    let index_type = ident(rd.index_size.rust_unsigned_integer());
+    let address_type = rd.address_size.rust_type();
+
+    let values = ["u8", "u16", "u32", "u64"].iter().map(|value_type| {
+        let value_type = ident(value_type);
+        quote! {
+            impl MockValue for #value_type {
+                fn to_u64(self) -> u64 {
+                    self as u64
+                }
+
+                fn from_u64(value: u64) -> Self {
+                    value as #value_type
+                }
+            }
+        }
+    });
+
+    let expectation = quote! {
+        #[doc = "Position of an access in the access log"]
+        pub struct Expectation<'a, G: MockGroup> {
+            io: &'a MockIo<G>,
+            position: usize,
+        }
+
+        impl <'a, G: MockGroup> Expectation<'a, G> {
+            #[doc = "Panics if register `register` is not written after this access."]
+            #[doc = "Returns the first write after this access."]
+            pub fn before_write(self, register: &str) -> Expectation<'a, G> {
+                match self.io.find_write(register, None, self.position + 1) {
+                    Some(position) => Expectation { io: self.io, position },
+                    None => panic!("register '{}' was not written after access {:?}, access log: {:?}", register, self.io.log[self.position], self.io.log),
+                }
+            }
+
+            pub fn access(&self) -> &'a Access {
+                &self.io.log[self.position]
+            }
+        }
+    };
+
+    let io_traits = [
+        ("RegisterIndexIoR", "RegisterIndexIoW", "Index", "index", &index_type),
+        ("RegisterAbsIoR", "RegisterAbsIoW", "Absolute", "abs_address", &address_type),
+        ("RegisterRelIoR", "RegisterRelIoW", "Relative", "rel_address", &address_type),
+    ];
+
+    let io_impls = io_traits.iter().map(|(read_trait, write_trait, location, location_name, location_type)| {
+        let location = ident(location);
+        let location_name = ident(location_name);
+
+        let io_impl = |read_trait: Ident, write_trait: Ident, asyncness: TokenStream| quote! {
+            impl <G: MockGroup, U: MockValue> #read_trait<G, U> for MockIo<G> {
+                #asyncness fn read(&mut self, #location_name: #location_type) -> U {
+                    U::from_u64(self.access(Op::Read, Location::#location(#location_name as u64), None))
+                }
+            }
+
+            impl <G: MockGroup, U: MockValue> #write_trait<G, U> for MockIo<G> {
+                #asyncness fn write(&mut self, #location_name: #location_type, value: U) {
+                    self.access(Op::Write, Location::#location(#location_name as u64), Some(value.to_u64()));
+                }
+            }
+        };
+
+        let sync_impl = io_impl(ident(read_trait), ident(write_trait), quote! {});
+        let async_impl = if options.async_io {
+            io_impl(ident(format!("Async{}", read_trait)), ident(format!("Async{}", write_trait)), quote! { async })
+        } else {
+            quote! {}
+        };
+
+        quote! {
+            #sync_impl
+            #async_impl
+        }
+    });
+
+    let async_traits = if options.async_io {
+        quote! { use super::register_trait_async::*; }
+    } else {
+        quote! {}
+    };
+
+    quote! {
+        pub mod mock_io {
+            extern crate alloc;
+
+            use alloc::{collections::BTreeMap, vec::Vec};
+            use core::marker::PhantomData;
+
+            use super::register_trait::*;
+            #async_traits
+            pub use super::register_access::{Location, Op};
+
+            #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+            pub struct Access {
+                pub op: Op,
+                pub location: Location,
+                pub value: u64,
+                #[doc = "Register name from the register description"]
+                pub register: Option<&'static str>,
+            }
+
+            #[doc = "Register group which can be used with `MockIo`"]
+            pub trait MockGroup: RegisterGroup {
+                fn register_name(op: Op, location: Location) -> Option<&'static str>;
+            }
+
+            pub trait MockValue: Sized {
+                fn to_u64(self) -> u64;
+                fn from_u64(value: u64) -> Self;
+            }
+
+            #( #values )*
+
+            #[doc = "In-memory IO for testing"]
+            #[doc = ""]
+            #[doc = "Register contents are stored by register location and"]
+            #[doc = "every register access is recorded to the access log."]
+            #[doc = ""]
+            #[doc = "Use `InfallibleIo<MockIo<G>>` with fallible register IO traits."]
+            pub struct MockIo<G: MockGroup> {
+                registers: BTreeMap<Location, u64>,
+                log: Vec<Access>,
+                _group: PhantomData<G>,
+            }
+
+            impl <G: MockGroup> Default for MockIo<G> {
+                fn default() -> Self {
+                    Self::new()
+                }
+            }
+
+            impl <G: MockGroup> MockIo<G> {
+                pub fn new() -> Self {
+                    Self {
+                        registers: BTreeMap::new(),
+                        log: Vec::new(),
+                        _group: PhantomData,
+                    }
+                }
+
+                #[doc = "Sets register contents without adding an access log entry"]
+                pub fn set(&mut self, location: Location, value: u64) {
+                    self.registers.insert(location, value);
+                }
+
+                #[doc = "Register contents. Default value is zero."]
+                pub fn get(&self, location: Location) -> u64 {
+                    self.registers.get(&location).copied().unwrap_or(0)
+                }
+
+                pub fn log(&self) -> &[Access] {
+                    &self.log
+                }
+
+                pub fn clear_log(&mut self) {
+                    self.log.clear();
+                }
+
+                #[doc = "Values written to register `register`"]
+                pub fn written(&self, register: &str) -> Vec<u64> {
+                    self.log.iter()
+                        .filter(|a| a.op == Op::Write && a.register == Some(register))
+                        .map(|a| a.value)
+                        .collect()
+                }
+
+                #[doc = "Panics if register `register` was not written `value`."]
+                #[doc = "Returns the first matching write."]
+                pub fn expect_write(&self, register: &str, value: u64) -> Expectation<'_, G> {
+                    match self.find_write(register, Some(value), 0) {
+                        Some(position) => Expectation { io: self, position },
+                        None => panic!("register '{}' was not written {:#x}, access log: {:?}", register, value, self.log),
+                    }
+                }
+
+                #[doc = "Position of the first write to register `register` starting from log position `start`"]
+                fn find_write(&self, register: &str, value: Option<u64>, start: usize) -> Option<usize> {
+                    self.log.iter()
+                        .skip(start)
+                        .position(|a| {
+                            a.op == Op::Write && a.register == Some(register) && value.map(|v| v == a.value).unwrap_or(true)
+                        })
+                        .map(|position| position + start)
+                }
+
+                fn access(&mut self, op: Op, location: Location, write_value: Option<u64>) -> u64 {
+                    let value = match write_value {
+                        Some(value) => {
+                            self.registers.insert(location, value);
+                            value
+                        }
+                        None => self.get(location),
+                    };
+
+                    self.log.push(Access {
+                        op,
+                        location,
+                        value,
+                        register: G::register_name(op, location),
+                    });
+
+                    value
+                }
+            }
+
+            #expectation
+            #( #io_impls )*
+        }
+    }
+}
+
+pub fn mock_group_impl(registers: &[Register], group_type: &Ident) -> TokenStream {
+    let mut name_arms = vec![];
+
+    for r in registers {
+        let name = r.name.as_str();
+
+        if let AccessMode::Read | AccessMode::ReadWrite = r.access_mode {
+            let location = access_location(r.read_location);
+            name_arms.push(quote! { (Op::Read, #location) => Some(#name), });
+        }
+
+        if let AccessMode::Write | AccessMode::ReadWrite = r.access_mode {
+            let location = access_location(r.write_location);
+            name_arms.push(quote! { (Op::Write, #location) => Some(#name), });
+        }
+    }
+
+    quote! {
+        impl super::mock_io::MockGroup for #group_type {
+            #[allow(unreachable_patterns)]
+            fn register_name(op: super::mock_io::Op, location: super::mock_io::Location) -> Option<&'static str> {
+                use super::mock_io::{Op, Location};
+
+                match (op, location) {
+                    #( #name_arms )*
+                    _ => None,
+                }
+            }
+        }
+
+        #[doc = "In-memory IO for testing this register group"]
+        pub type MockIo = super::mock_io::MockIo<#group_type>;
+    }
+}
@@ -0,0 +1,174 @@
+
+use quote::quote;
+
+use proc_macro2::TokenStream;
+
+use crate::logic::validation::{
+    ParsedFile,
+    register::{
+        RegisterSize,
+        RegisterLocation,
+        AccessMode,
+    },
+};
+
+use super::ident;
+
+/// Errors for registers which can't be accessed with x86 port IO.
+/// IO ports are 16-bit, and registers and the index port are at most
+/// 32-bit.
+pub fn check_port_io(parsed_file: &ParsedFile) -> Vec<String> {
+    let mut errors = vec![];
+
+    for r in parsed_file.all_registers() {
+        if r.size_in_bits == RegisterSize::Size64 {
+            errors.push(format!("register '{}' is 64-bit, x86 port IO supports at most 32-bit registers", r.name.as_str()));
+        }
+
+        let locations = match r.access_mode {
+            AccessMode::Read => vec![r.read_location],
+            AccessMode::Write => vec![r.write_location],
+            AccessMode::ReadWrite => vec![r.read_location, r.write_location],
+        };
+
+        for location in locations {
+            match location {
+                RegisterLocation::Absolute(address) if address > u64::from(u16::MAX) => {
+                    errors.push(format!("register '{}' has absolute address {:#x} which is not a 16-bit IO port", r.name.as_str(), address));
+                }
+                RegisterLocation::Index(index) if index > u64::from(u32::MAX) => {
+                    errors.push(format!("register '{}' has index {:#x} which doesn't fit to the 32-bit index port", r.name.as_str(), index));
+                }
+                _ => (),
+            }
+        }
+    }
+
+    errors.dedup();
+    errors
+}
+
+pub fn port_io_module(parsed_file: &ParsedFile) -> TokenStream {
+    let rd = &parsed_file.description;
+    let index_type = ident(rd.index_size.rust_unsigned_integer());
+    let address_type = rd.address_size.rust_type();
+
+    // x86 port IO supports at most 32-bit values.
+    let index_port_type = match rd.index_size {
+        RegisterSize::Size64 => RegisterSize::Size32,
+        size => size,
+    };
+    let index_port_type = ident(index_port_type.rust_unsigned_integer());
+
+    let port_values = [("u8", "al"), ("u16", "ax"), ("u32", "eax")].iter().map(|(value_type, register)| {
+        let value_type = ident(value_type);
+        let in_instruction = format!("in {}, dx", register);
+        let out_instruction = format!("out dx, {}", register);
+        quote! {
+            impl PortValue for #value_type {
+                #[inline]
+                unsafe fn read_port(port: u16) -> Self {
+                    let value: Self;
+                    unsafe {
+                        core::arch::asm!(#in_instruction, out(#register) value, in("dx") port, options(nomem, nostack, preserves_flags));
+                    }
+                    value
+                }
+
+                #[inline]
+                unsafe fn write_port(port: u16, value: Self) {
+                    unsafe {
+                        core::arch::asm!(#out_instruction, in("dx") port, in(#register) value, options(nomem, nostack, preserves_flags));
+                    }
+                }
+            }
+        }
+    });
+
+    quote! {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        pub mod port_io {
+            use super::register_trait::*;
+
+            #[doc = "Value which can be transferred with x86 `in` and `out` instructions"]
+            pub trait PortValue: Sized {
+                #[doc = "# Safety"]
+                #[doc = ""]
+                #[doc = "Reading the port must not break memory safety."]
+                unsafe fn read_port(port: u16) -> Self;
+
+                #[doc = "# Safety"]
+                #[doc = ""]
+                #[doc = "Writing the port must not break memory safety."]
+                unsafe fn write_port(port: u16, value: Self);
+            }
+
+            #( #port_values )*
+
+            #[doc = "x86 port IO"]
+            #[doc = ""]
+            #[doc = "Absolute register address is the IO port. Index based registers are"]
+            #[doc = "accessed by writing the index to the index port and then accessing the data port."]
+            #[doc = ""]
+            #[doc = "On x86_64 Linux user space programs can access IO ports after"]
+            #[doc = "`ioperm(first_port, count, 1)` or `iopl(3)` system call. Both require"]
+            #[doc = "capability `CAP_SYS_RAWIO`, so tests using `PortIo` are run as root."]
+            #[doc = "Without the permission port access fails with `SIGSEGV`."]
+            pub struct PortIo {
+                index_port: u16,
+                data_port: u16,
+            }
+
+            impl PortIo {
+                #[doc = "# Safety"]
+                #[doc = ""]
+                #[doc = "IO ports of the registers, `index_port` and `data_port` must be valid for reads and writes."]
+                pub unsafe fn new(index_port: u16, data_port: u16) -> Self {
+                    Self { index_port, data_port }
+                }
+
+                pub fn index_port(&self) -> u16 {
+                    self.index_port
+                }
+
+                pub fn data_port(&self) -> u16 {
+                    self.data_port
+                }
+            }
+
+            impl <G: RegisterGroup, U: PortValue> RegisterAbsIoR<G, U> for PortIo {
+                #[inline]
+                fn read(&mut self, abs_address: #address_type) -> U {
+                    unsafe { U::read_port(abs_address as u16) }
+                }
+            }
+
+            impl <G: RegisterGroup, U: PortValue> RegisterAbsIoW<G, U> for PortIo {
+                #[inline]
+                fn write(&mut self, abs_address: #address_type, value: U) {
+                    unsafe { U::write_port(abs_address as u16, value) }
+                }
+            }
+
+            impl <G: RegisterGroup, U: PortValue> RegisterIndexIoR<G, U> for PortIo {
+                #[inline]
+                fn read(&mut self, index: #index_type) -> U {
+                    unsafe {
+                        #index_port_type::write_port(self.index_port, index as #index_port_type);
+                        U::read_port(self.data_port)
+                    }
+                }
+            }
+
+            impl <G: RegisterGroup, U: PortValue> RegisterIndexIoW<G, U> for PortIo {
+                #[inline]
+                fn write(&mut self, index: #index_type, value: U) {
+                    unsafe {
+                        #index_port_type::write_port(self.index_port, index as #index_port_type);
+                        U::write_port(self.data_port, value)
+                    }
+                }
+            }
+        }
+    }
+}
@@ -12,6 +12,7 @@ use crate::logic::{
     validation::{
         register::{
             RegisterLocation,
+            RegisterSize,
             Register,
             AccessMode,
             RegisterFunction,
@@ -25,28 +26,27 @@ use crate::logic::{
     },
 };
 
-use super::{ident, lit_int};
-
-
-pub fn register_group(registers: &Vec<Register>, group_type: &Ident, group_name: &str) -> TokenStream {
+use crate::config::RustOptions;
 
-    let mut unique_register_traits: HashSet<String> = HashSet::new();
-    let mut register_traits: Vec<TokenStream> = vec![];
+use super::{ident, lit_int};
 
-    for r in registers {
-        for io_trait in r.io_traits_rust(group_type) {
-            if unique_register_traits.insert(io_trait.to_string()) {
-                register_traits.push(io_trait);
-            }
-        }
-    }
 
-    let type_bounds = quote! { #( #register_traits )+* };
+pub fn register_group(registers: &[Register], group_type: &Ident, group_name: &str, options: &RustOptions) -> TokenStream {
+    let type_bounds = group_type_bounds(registers, |r| r.io_traits_rust(group_type, options));
+    let struct_type_param = struct_type_param(&type_bounds, options);
 
     let register_getters_type = ident(format!("{}Registers", &group_name));
 
     let register_types_rust: Vec<Ident> = registers.iter().map(|r| r.register_rust_name()).collect();
-    let register_types_rust_copy = register_types_rust.clone();
+    let register_values_rust: Vec<TokenStream> = registers.iter().map(|r| {
+        let name = r.register_rust_name();
+        if r.has_shadow(options) {
+            let getter = r.register_getter_rust_name();
+            quote! { #name { io: &mut self.io, shadow: &mut self.shadow.#getter } }
+        } else {
+            quote! { #name { io: &mut self.io } }
+        }
+    }).collect();
     let register_getters_rust: Vec<Ident> = registers.iter().map(|r| r.register_getter_rust_name()).collect();
     let docs: Vec<TokenStream> = registers.iter().map(|r| r.description_rust()).collect();
 
@@ -57,50 +57,280 @@ pub fn register_group(registers: &Vec<Register>, group_type: &Ident, group_name:
         .map(|r| r.register_getter_rust_name())
         .collect();
 
+    let (snapshot_struct, snapshot, snapshot_async) = if options.snapshot {
+        snapshot_methods(registers, options)
+    } else {
+        (quote! {}, quote! {}, quote! {})
+    };
+
+    let async_impl = if options.async_io {
+        let async_type_bounds = group_type_bounds(registers, |r| r.async_io_traits_rust(group_type));
+        let getters = &register_getters_read_access_rust;
+        quote! {
+            impl <T: #async_type_bounds > #register_getters_type<T> {
+                pub async fn debug_registers_async<F: FnMut(&dyn core::fmt::Debug)>(&mut self, mut f: F) {
+                    #(
+                        (f)(&self.#getters().read_async().await);
+                    )*
+                }
+
+                #snapshot_async
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let debug_registers = if options.fallible {
+        quote! {
+            pub fn debug_registers<F: FnMut(&dyn core::fmt::Debug)>(&mut self, mut f: F) -> Result<(), TryIoError<T>> {
+                #(
+                    (f)(&self.#register_getters_read_access_rust().read()?);
+                )*
+                Ok(())
+            }
+        }
+    } else {
+        quote! {
+            pub fn debug_registers<F: FnMut(&dyn core::fmt::Debug)>(&mut self, mut f: F) {
+                #(
+                    (f)(&self.#register_getters_read_access_rust().read());
+                )*
+            }
+        }
+    };
+
+    let (shadow_struct, shadow_field, shadow_init) = if registers.iter().any(|r| r.has_shadow(options)) {
+        let shadow_registers = registers.iter().filter(|r| r.has_shadow(options));
+        let getters = shadow_registers.clone().map(|r| r.register_getter_rust_name());
+        let sizes = shadow_registers.map(|r| ident(r.size_in_bits.rust_unsigned_integer()));
+        (
+            quote! {
+                #[doc = "Last written values of write-only registers"]
+                #[derive(Debug, Default, Clone, Copy)]
+                struct Shadow {
+                    #( #getters: Option<#sizes>, )*
+                }
+            },
+            quote! { shadow: Shadow, },
+            quote! { shadow: Shadow::default(), },
+        )
+    } else {
+        (quote! {}, quote! {}, quote! {})
+    };
+
     quote! {
-        pub struct #register_getters_type<T: #type_bounds > {
+        #shadow_struct
+        #snapshot_struct
+
+        pub struct #register_getters_type<#struct_type_param> {
             io: T,
+            #shadow_field
         }
 
-        impl <T: #type_bounds > #register_getters_type<T> {
+        impl <#struct_type_param> #register_getters_type<T> {
             #[inline]
             pub fn new(io: T) -> Self {
                 Self {
-                    io
+                    io,
+                    #shadow_init
                 }
             }
 
+            #[inline]
+            pub fn io(&self) -> &T {
+                &self.io
+            }
+
+            #[inline]
+            pub fn into_io(self) -> T {
+                self.io
+            }
+
             #(
                 #docs
                 #[inline]
                 pub fn #register_getters_rust(&mut self) -> #register_types_rust<'_, T> {
-                    #register_types_rust_copy {
-                        io: &mut self.io
-                    }
+                    #register_values_rust
                 }
             )*
+        }
 
-            pub fn debug_registers<F: FnMut(&dyn core::fmt::Debug)>(&mut self, mut f: F) {
-                #(
-                    (f)(&self.#register_getters_read_access_rust().read());
-                )*
-            }
+        impl <T: #type_bounds > #register_getters_type<T> {
+            #debug_registers
+            #snapshot
         }
 
+        #async_impl
+
         pub struct #group_type;
         impl RegisterGroup for #group_type {}
 
     }
 }
 
-pub fn registers_to_module(registers: &Vec<Register>, rd: &RegisterDescription, group_type: &Ident) -> TokenStream {
+/// The `Snapshot` type and register group methods `save` and `restore`.
+/// With async IO, also methods `save_async` and `restore_async`.
+fn snapshot_methods(registers: &[Register], options: &RustOptions) -> (TokenStream, TokenStream, TokenStream) {
+    let mut fields = vec![];
+
+    for r in registers {
+        let getter = r.register_getter_rust_name();
+        let size = ident(r.size_in_bits.rust_unsigned_integer());
+        let doc = format!("Register `{}`", r.name.as_str());
+
+        match r.access_mode {
+            AccessMode::Read | AccessMode::ReadWrite => {
+                fields.push(quote! {
+                    #[doc = #doc]
+                    pub #getter: #size,
+                });
+            }
+            AccessMode::Write => {
+                fields.push(quote! {
+                    #[doc = #doc]
+                    #[doc = ""]
+                    #[doc = "Last written value. Register is not restored if the value is `None`."]
+                    pub #getter: Option<#size>,
+                });
+            }
+        }
+    }
+
+    let snapshot_struct = quote! {
+        #[doc = "Saved register values of the group"]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub struct Snapshot {
+            #( #fields )*
+        }
+    };
+
+    let methods = snapshot_save_and_restore(registers, options.fallible, false);
+
+    let async_methods = if options.async_io {
+        snapshot_save_and_restore(registers, false, true)
+    } else {
+        quote! {}
+    };
+
+    (snapshot_struct, methods, async_methods)
+}
+
+fn snapshot_save_and_restore(registers: &[Register], fallible: bool, async_io: bool) -> TokenStream {
+    let (result, unit_result, ok_unit, propagate_error) = if fallible {
+        (quote! { Result<Snapshot, TryIoError<T>> }, quote! { -> Result<(), TryIoError<T>> }, quote! { Ok(()) }, quote! { ? })
+    } else if async_io {
+        (quote! { Snapshot }, quote! {}, quote! {}, quote! { .await })
+    } else {
+        (quote! { Snapshot }, quote! {}, quote! {}, quote! {})
+    };
+    let (asyncness, method_postfix) = if async_io {
+        (quote! { async }, "_async")
+    } else {
+        (quote! {}, "")
+    };
+
+    let save = ident(format!("save{}", method_postfix));
+    let restore = ident(format!("restore{}", method_postfix));
+    let read_raw = ident(format!("read_raw{}", method_postfix));
+    let write_raw = ident(format!("write_raw{}", method_postfix));
+
+    let values = registers.iter().map(|r| {
+        let getter = r.register_getter_rust_name();
+        match r.access_mode {
+            AccessMode::Read | AccessMode::ReadWrite => quote! { #getter: self.#getter().#read_raw() #propagate_error, },
+            AccessMode::Write => quote! { #getter: self.shadow.#getter, },
+        }
+    });
+
+    let mut restore_registers: Vec<&Register> = registers.iter()
+        .filter(|r| r.access_mode != AccessMode::Read)
+        .collect();
+    restore_registers.sort_by_key(|r| r.restore_order.unwrap_or(0));
+
+    let restore_writes = restore_registers.iter().map(|r| {
+        let getter = r.register_getter_rust_name();
+        match r.access_mode {
+            AccessMode::Write => quote! {
+                if let Some(value) = snapshot.#getter {
+                    self.#getter().#write_raw(value) #propagate_error;
+                }
+            },
+            _ => quote! {
+                self.#getter().#write_raw(snapshot.#getter) #propagate_error;
+            },
+        }
+    });
+
+    let snapshot_value = quote! {
+        Snapshot {
+            #( #values )*
+        }
+    };
+    let snapshot_value = if fallible {
+        quote! { Ok(#snapshot_value) }
+    } else {
+        snapshot_value
+    };
+
+    quote! {
+        #[doc = "Saves values of readable registers and last written values of write-only registers"]
+        pub #asyncness fn #save(&mut self) -> #result {
+            #snapshot_value
+        }
+
+        #[doc = "Writes saved register values back to writable registers"]
+        #[doc = ""]
+        #[doc = "Registers are written in the order specified with register"]
+        #[doc = "description key `restore_order`."]
+        pub #asyncness fn #restore(&mut self, snapshot: &Snapshot) #unit_result {
+            #( #restore_writes )*
+            #ok_unit
+        }
+    }
+}
+
+/// Write-only registers have shadow storage for their last written value.
+fn shadow_storage(options: &RustOptions) -> bool {
+    options.snapshot || options.shadow
+}
+
+/// IO trait bounds required by all registers of the group.
+fn group_type_bounds<F: Fn(&Register) -> Vec<TokenStream>>(registers: &[Register], io_traits: F) -> TokenStream {
+    let mut unique_register_traits: HashSet<String> = HashSet::new();
+    let mut register_traits: Vec<TokenStream> = vec![];
+
+    for r in registers {
+        for io_trait in io_traits(r) {
+            if unique_register_traits.insert(io_trait.to_string()) {
+                register_traits.push(io_trait);
+            }
+        }
+    }
+
+    quote! { #( #register_traits )+* }
+}
+
+/// Type parameter `T` for register and register group structs.
+///
+/// With async IO the same struct is used with both blocking and async IO,
+/// so IO trait bounds are only set for the methods.
+fn struct_type_param(type_bounds: &TokenStream, options: &RustOptions) -> TokenStream {
+    if options.async_io {
+        quote! { T }
+    } else {
+        quote! { T: #type_bounds }
+    }
+}
+
+pub fn registers_to_module(registers: &[Register], rd: &RegisterDescription, group_type: &Ident, options: &RustOptions) -> TokenStream {
 
     let mut register_modules: Vec<TokenStream> = vec![];
     for r in registers {
         let module_name = ident(r.name.as_str().to_snake_case());
-        let module = register_module(r,);
-        let r_struct = register_struct(r, group_type);
-        let r_struct_impl = register_struct_impl(r, rd, group_type);
+        let module = register_module(r, options);
+        let r_struct = register_struct(r, group_type, options);
+        let r_struct_impl = register_struct_impl(r, rd, group_type, options);
         let tokens = quote! {
             #r_struct
             pub mod #module_name {
@@ -132,32 +362,47 @@ impl Register {
         ident(self.name.as_str().to_snake_case())
     }
 
-    fn io_traits_rust(&self, group_type: &Ident) -> Vec<TokenStream> {
+    fn io_traits_rust(&self, group_type: &Ident, options: &RustOptions) -> Vec<TokenStream> {
+        let prefix = if options.fallible { "Try" } else { "" };
+        self.io_traits_with_prefix(group_type, prefix)
+    }
+
+    fn async_io_traits_rust(&self, group_type: &Ident) -> Vec<TokenStream> {
+        self.io_traits_with_prefix(group_type, "Async")
+    }
+
+    fn io_traits_with_prefix(&self, group_type: &Ident, prefix: &str) -> Vec<TokenStream> {
         let size = ident(self.size_in_bits.rust_unsigned_integer());
 
         let mut r = vec![];
 
         if let AccessMode::Read | AccessMode::ReadWrite = self.access_mode {
             let read = match &self.read_location {
-                RegisterLocation::Index(_) => quote! { RegisterIndexIoR<#group_type, #size> },
-                RegisterLocation::Absolute(_) => quote! { RegisterAbsIoR<#group_type, #size> },
-                RegisterLocation::Relative(_) => quote! { RegisterRelIoR<#group_type, #size> },
+                RegisterLocation::Index(_) => "RegisterIndexIoR",
+                RegisterLocation::Absolute(_) => "RegisterAbsIoR",
+                RegisterLocation::Relative(_) => "RegisterRelIoR",
             };
-            r.push(read);
+            let read = ident(format!("{}{}", prefix, read));
+            r.push(quote! { #read<#group_type, #size> });
         }
 
         if let AccessMode::Write | AccessMode::ReadWrite = self.access_mode {
             let write = match &self.write_location {
-                RegisterLocation::Index(_) => quote! { RegisterIndexIoW<#group_type, #size> },
-                RegisterLocation::Absolute(_) => quote! { RegisterAbsIoW<#group_type, #size> },
-                RegisterLocation::Relative(_) => quote! { RegisterRelIoW<#group_type, #size> },
+                RegisterLocation::Index(_) => "RegisterIndexIoW",
+                RegisterLocation::Absolute(_) => "RegisterAbsIoW",
+                RegisterLocation::Relative(_) => "RegisterRelIoW",
             };
-            r.push(write);
+            let write = ident(format!("{}{}", prefix, write));
+            r.push(quote! { #write<#group_type, #size> });
         }
 
         r
     }
 
+    fn has_shadow(&self, options: &RustOptions) -> bool {
+        self.access_mode == AccessMode::Write && shadow_storage(options)
+    }
+
     fn contains_reserved_bit_fields(&self) -> bool {
         for bit_field in &self.functions {
             if let FunctionStatus::Reserved = &bit_field.status {
@@ -169,23 +414,33 @@ impl Register {
     }
 }
 
-fn register_struct(r: &Register, group_type: &Ident) -> TokenStream {
+fn register_struct(r: &Register, group_type: &Ident, options: &RustOptions) -> TokenStream {
     let name = r.register_rust_name();
-    let io_traits = r.io_traits_rust(group_type);
-    let type_bound = quote! { #( #io_traits )+* };
+    let io_traits = r.io_traits_rust(group_type, options);
+    let type_bounds = quote! { #( #io_traits )+* };
+    let struct_type_param = struct_type_param(&type_bounds, options);
     let doc = r.description_rust();
+    let shadow = if r.has_shadow(options) {
+        let size = ident(r.size_in_bits.rust_unsigned_integer());
+        quote! { shadow: &'a mut Option<#size>, }
+    } else {
+        quote! {}
+    };
+
     quote! {
         #doc
-        pub struct #name<'a, T: #type_bound> {
+        pub struct #name<'a, #struct_type_param> {
             io: &'a mut T,
+            #shadow
         }
     }
 }
 
-fn location_trait(r: &Register, rd: &RegisterDescription, group_type: &Ident, location: RegisterLocation, const_postfix: &str, trait_postfix: &str) -> (TokenStream, Ident) {
+fn location_trait(r: &Register, rd: &RegisterDescription, group_type: &Ident, options: &RustOptions, location: RegisterLocation, const_postfix: &str, trait_postfix: &str) -> (TokenStream, Ident) {
     let name = r.register_rust_name();
-    let io_traits = r.io_traits_rust(group_type);
+    let io_traits = r.io_traits_rust(group_type, options);
     let type_bounds = quote! { #( #io_traits )+* };
+    let struct_type_param = struct_type_param(&type_bounds, options);
     let index_const_type = ident(rd.index_size.rust_unsigned_integer());
     let address_const_type = rd.address_size.rust_type();
 
@@ -211,19 +466,112 @@ fn location_trait(r: &Register, rd: &RegisterDescription, group_type: &Ident, lo
     };
 
     (quote! {
-        impl <'a, T: #type_bounds> #trait_name for super::#name<'a, T> {
+        impl <'a, #struct_type_param> #trait_name for super::#name<'a, T> {
             const #const_name: #const_type = #const_value;
         }
     }, const_name)
 }
 
-fn register_struct_impl(r: &Register, rd: &RegisterDescription, group_type: &Ident) -> TokenStream {
+fn register_struct_impl(r: &Register, rd: &RegisterDescription, group_type: &Ident, options: &RustOptions) -> TokenStream {
     let name = r.register_rust_name();
-    let io_traits = r.io_traits_rust(group_type);
+    let io_traits = r.io_traits_rust(group_type, options);
     let type_bounds = quote! { #( #io_traits )+* };
+    let struct_type_param = struct_type_param(&type_bounds, options);
+
+    let (read_location_trait_impl, read_location_const) = location_trait(r, rd, group_type, options, r.read_location, "_R", "R");
+    let (write_location_trait_impl, write_location_const) = location_trait(r, rd, group_type, options, r.write_location, "_W", "W");
+
+    let methods = register_methods(r, &RegisterIo::new(options, &read_location_const, &write_location_const), options);
+
+    let location_trait_impl = match r.access_mode {
+        AccessMode::Write => quote! { #write_location_trait_impl },
+        AccessMode::Read => quote! { #read_location_trait_impl },
+        AccessMode::ReadWrite => quote! {
+            #read_location_trait_impl
+            #write_location_trait_impl
+        },
+    };
+
+    let new = if r.has_shadow(options) {
+        let size = ident(r.size_in_bits.rust_unsigned_integer());
+        let last_written = if options.shadow {
+            quote! {
+                #[doc = "Last value written to the register"]
+                #[inline]
+                pub fn last_written(&self) -> Option<W> {
+                    self.shadow.map(|raw_bits| W { raw_bits })
+                }
+            }
+        } else {
+            quote! {}
+        };
 
-    let (read_location_trait_impl, read_location_const) = location_trait(r, rd, group_type, r.read_location, "_R", "R");
-    let (write_location_trait_impl, write_location_const) = location_trait(r, rd, group_type, r.write_location, "_W", "W");
+        quote! {
+            #[doc = "Argument `shadow` stores the last written value of the register"]
+            pub fn new(io: &'a mut T, shadow: &'a mut Option<#size>) -> Self {
+                Self { io, shadow }
+            }
+
+            #last_written
+        }
+    } else {
+        quote! {
+            pub fn new(io: &'a mut T) -> Self {
+                Self { io }
+            }
+        }
+    };
+
+    let async_impl = if options.async_io {
+        let async_io_traits = r.async_io_traits_rust(group_type);
+        let async_methods = register_methods(r, &RegisterIo::new_async(&read_location_const, &write_location_const), options);
+        quote! {
+            use super::super::register_trait_async::*;
+
+            impl <'a, T: #( #async_io_traits )+*> super::#name<'a, T> {
+                #( #async_methods )*
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    quote! {
+        use super::super::register_trait::*;
+        use super::#group_type;
+
+        #location_trait_impl
+
+        impl <'a, #struct_type_param> InGroup for super::#name<'a, T> {
+            type Group = #group_type;
+        }
+
+        impl <'a, #struct_type_param> super::#name<'a, T> {
+            #new
+        }
+
+        impl <'a, T: #type_bounds> super::#name<'a, T> {
+            #( #methods )*
+        }
+
+        #async_impl
+    }
+}
+
+/// Register read, write and modify methods.
+fn register_methods(r: &Register, io: &RegisterIo, options: &RustOptions) -> Vec<TokenStream> {
+    let RegisterIo { read_value, write_raw_bits, unit_result, read_result, ok_unit, propagate_error, asyncness, method_postfix, .. } = io;
+    let size = ident(r.size_in_bits.rust_unsigned_integer());
+    let modify = ident(format!("modify{}", method_postfix));
+    let read = ident(format!("read{}", method_postfix));
+    let write = ident(format!("write{}", method_postfix));
+    let write_value = ident(format!("write_value{}", method_postfix));
+
+    let record_shadow = if r.has_shadow(options) {
+        quote! { *self.shadow = Some(w.raw_bits); }
+    } else {
+        quote! {}
+    };
 
     let mut methods = vec![];
 
@@ -231,24 +579,51 @@ fn register_struct_impl(r: &Register, rd: &RegisterDescription, group_type: &Ide
         methods.push(quote! {
             #[doc = "Modifies the contents of the register"]
             #[inline]
-            pub fn modify<F>(&mut self, f: F)
+            pub #asyncness fn #modify<F>(&mut self, f: F) #unit_result
             where
                 for<'w> F: FnOnce(&R, &'w mut W) -> &'w mut W,
             {
-                let r = self.read();
+                let r = self.#read() #propagate_error;
                 let mut w = W { raw_bits: r.raw_bits };
                 (f)(&r, &mut w);
-                self.io.write(Self::#write_location_const, w.raw_bits);
+                #write_raw_bits
+                #ok_unit
+            }
+        });
+    }
+
+    if r.has_shadow(options) && options.shadow {
+        methods.push(quote! {
+            #[doc = "Modifies the last written value of the register and writes it to the register"]
+            #[doc = ""]
+            #[doc = "Modification starts from zero if the register is not written yet."]
+            #[inline]
+            pub #asyncness fn #modify<F>(&mut self, f: F) #unit_result
+            where
+                for<'w> F: FnOnce(&'w mut W) -> &'w mut W,
+            {
+                let mut w = W { raw_bits: self.shadow.unwrap_or(0) };
+                (f)(&mut w);
+                #write_raw_bits
+                #record_shadow
+                #ok_unit
             }
         });
     }
 
     if let AccessMode::Read | AccessMode::ReadWrite = r.access_mode {
+        let r_value = quote! { R { raw_bits: #read_value } };
+        let r_value = if io.fallible {
+            quote! { Ok(#r_value) }
+        } else {
+            r_value
+        };
+
         methods.push(quote! {
             #[doc = "Reads the contents of the register"]
             #[inline]
-            pub fn read(&mut self) -> R {
-                R { raw_bits: self.io.read(Self::#read_location_const) }
+            pub #asyncness fn #read(&mut self) #read_result {
+                #r_value
             }
         });
     }
@@ -258,63 +633,147 @@ fn register_struct_impl(r: &Register, rd: &RegisterDescription, group_type: &Ide
             methods.push(quote! {
                 #[doc = "Writes to the register"]
                 #[inline]
-                pub fn write<F>(&mut self, f: F)
+                pub #asyncness fn #write<F>(&mut self, f: F) #unit_result
                 where
                     F: FnOnce(&mut W) -> &mut W,
                 {
                     let mut w = W { raw_bits: 0 };
                     (f)(&mut w);
-                    self.io.write(Self::#write_location_const, w.raw_bits);
+                    #write_raw_bits
+                    #record_shadow
+                    #ok_unit
                 }
             });
+
+            if options.const_fn {
+                methods.push(quote! {
+                    #[doc = "Writes value `w` to the register"]
+                    #[inline]
+                    pub #asyncness fn #write_value(&mut self, w: W) #unit_result {
+                        #write_raw_bits
+                        #record_shadow
+                        #ok_unit
+                    }
+                });
+            }
         }
     }
 
-    let location_trait_impl = match r.access_mode {
-        AccessMode::Write => quote! { #write_location_trait_impl },
-        AccessMode::Read => quote! { #read_location_trait_impl },
-        AccessMode::ReadWrite => quote! {
-            #read_location_trait_impl
-            #write_location_trait_impl
-        },
-    };
+    // Raw register access for saving and restoring register group snapshots.
+    if options.snapshot {
+        let read_raw = ident(format!("read_raw{}", method_postfix));
+        let write_raw = ident(format!("write_raw{}", method_postfix));
 
-    quote! {
-        use super::super::register_trait::*;
-        use super::#group_type;
+        if let AccessMode::Read | AccessMode::ReadWrite = r.access_mode {
+            let raw_value = if io.fallible {
+                quote! { Ok(#read_value) }
+            } else {
+                quote! { #read_value }
+            };
+            let raw_result = if io.fallible {
+                quote! { -> Result<#size, TryIoError<T>> }
+            } else {
+                quote! { -> #size }
+            };
 
-        #location_trait_impl
+            methods.push(quote! {
+                #[inline]
+                pub(super) #asyncness fn #read_raw(&mut self) #raw_result {
+                    #raw_value
+                }
+            });
+        }
 
-        impl <'a, T: #type_bounds> InGroup for super::#name<'a, T> {
-            type Group = #group_type;
+        if let AccessMode::Write | AccessMode::ReadWrite = r.access_mode {
+            methods.push(quote! {
+                #[inline]
+                pub(super) #asyncness fn #write_raw(&mut self, raw_bits: #size) #unit_result {
+                    let w = W { raw_bits };
+                    #write_raw_bits
+                    #record_shadow
+                    #ok_unit
+                }
+            });
         }
+    }
 
-        impl <'a, T: #type_bounds> super::#name<'a, T> {
-            pub fn new(io: &'a mut T) -> Self {
-                Self { io }
+    methods
+}
+
+/// Code snippets for register IO which depend on the selected IO trait flavor.
+///
+/// Writing code snippet writes variable `w` to the register.
+struct RegisterIo {
+    read_value: TokenStream,
+    write_raw_bits: TokenStream,
+    unit_result: TokenStream,
+    read_result: TokenStream,
+    ok_unit: TokenStream,
+    propagate_error: TokenStream,
+    asyncness: TokenStream,
+    method_postfix: &'static str,
+    fallible: bool,
+}
+
+impl RegisterIo {
+    fn new(options: &RustOptions, read_location_const: &Ident, write_location_const: &Ident) -> Self {
+        if options.fallible {
+            Self {
+                read_value: quote! { self.io.try_read(Self::#read_location_const)? },
+                write_raw_bits: quote! { self.io.try_write(Self::#write_location_const, w.raw_bits)?; },
+                unit_result: quote! { -> Result<(), TryIoError<T>> },
+                read_result: quote! { -> Result<R, TryIoError<T>> },
+                ok_unit: quote! { Ok(()) },
+                propagate_error: quote! { ? },
+                asyncness: quote! {},
+                method_postfix: "",
+                fallible: true,
+            }
+        } else {
+            Self {
+                read_value: quote! { self.io.read(Self::#read_location_const) },
+                write_raw_bits: quote! { self.io.write(Self::#write_location_const, w.raw_bits); },
+                unit_result: quote! {},
+                read_result: quote! { -> R },
+                ok_unit: quote! {},
+                propagate_error: quote! {},
+                asyncness: quote! {},
+                method_postfix: "",
+                fallible: false,
             }
+        }
+    }
 
-            #( #methods )*
+    fn new_async(read_location_const: &Ident, write_location_const: &Ident) -> Self {
+        Self {
+            read_value: quote! { self.io.read(Self::#read_location_const).await },
+            write_raw_bits: quote! { self.io.write(Self::#write_location_const, w.raw_bits).await; },
+            unit_result: quote! {},
+            read_result: quote! { -> R },
+            ok_unit: quote! {},
+            propagate_error: quote! { .await },
+            asyncness: quote! { async },
+            method_postfix: "_async",
+            fallible: false,
         }
     }
 }
 
-
-fn register_module(r: &Register) -> TokenStream {
+fn register_module(r: &Register, options: &RustOptions) -> TokenStream {
     let mut module_code: Vec<TokenStream> = vec![];
 
     let bit_fields_and_enums = bit_fields_and_enums(r);
 
     match r.access_mode {
         AccessMode::Read => {
-            module_code.push(read_register_code(&r, &bit_fields_and_enums));
+            module_code.push(read_register_code(r, &bit_fields_and_enums, options));
         },
         AccessMode::Write => {
-            module_code.push(write_register_code(&r, &bit_fields_and_enums));
+            module_code.push(write_register_code(r, &bit_fields_and_enums, options));
         },
         AccessMode::ReadWrite => {
-            module_code.push(read_register_code(&r, &bit_fields_and_enums));
-            module_code.push(write_register_code(&r, &bit_fields_and_enums));
+            module_code.push(read_register_code(r, &bit_fields_and_enums, options));
+            module_code.push(write_register_code(r, &bit_fields_and_enums, options));
         }
     }
 
@@ -323,7 +782,7 @@ fn register_module(r: &Register) -> TokenStream {
     }
 }
 
-fn read_register_code(r: &Register, bit_fields: &Vec<RegisterBitFieldAndEnum>) -> TokenStream {
+fn read_register_code(r: &Register, bit_fields: &Vec<RegisterBitFieldAndEnum>, options: &RustOptions) -> TokenStream {
     let size = ident(r.size_in_bits.rust_unsigned_integer());
 
     let mut r_methods: Vec<TokenStream> = vec![];
@@ -331,7 +790,7 @@ fn read_register_code(r: &Register, bit_fields: &Vec<RegisterBitFieldAndEnum>) -
     let mut r_debug: Vec<TokenStream> = vec![];
 
     for bit_field in bit_fields {
-        r_items.push(bit_field.read_code(&size));
+        r_items.push(bit_field.read_code(&size, options));
 
         let r_type = bit_field.read_enum_name();
         let getter = bit_field.snake_case_name();
@@ -357,6 +816,8 @@ fn read_register_code(r: &Register, bit_fields: &Vec<RegisterBitFieldAndEnum>) -
     }
 
     let register = r.name.as_str().to_constant_case();
+    let display_impl = display_impl(r, &ident("R"));
+    let value_trait_impls = value_trait_impls(r, bit_fields, EnumMode::Read, options);
 
     quote! {
         #[doc = "Value to write to the register"]
@@ -376,18 +837,46 @@ fn read_register_code(r: &Register, bit_fields: &Vec<RegisterBitFieldAndEnum>) -
             #( #r_methods )*
         }
 
+        #display_impl
+        #value_trait_impls
+
         #( #r_items )*
     }
 }
 
-fn write_register_code(r: &Register, bit_fields: &Vec<RegisterBitFieldAndEnum>) -> TokenStream {
+fn write_register_code(r: &Register, bit_fields: &Vec<RegisterBitFieldAndEnum>, options: &RustOptions) -> TokenStream {
     let size = ident(r.size_in_bits.rust_unsigned_integer());
 
     let mut w_methods: Vec<TokenStream> = vec![];
     let mut w_items: Vec<TokenStream> = vec![];
 
+    if options.const_fn {
+        w_methods.push(quote! {
+            #[doc = "Register value with all bits cleared"]
+            #[inline]
+            pub const fn new() -> Self {
+                W { raw_bits: 0 }
+            }
+        });
+    }
+
+    // Raw value is needed for inspecting built values and values from `last_written`.
+    if options.const_fn || options.shadow {
+        w_methods.push(quote! {
+            #[doc = "Raw register value"]
+            #[inline]
+            pub const fn bits(&self) -> #size {
+                self.raw_bits
+            }
+        });
+    }
+
     for bit_field in bit_fields {
-        w_items.push(bit_field.write_code(&size));
+        w_items.push(bit_field.write_code(&size, options));
+
+        if options.const_fn {
+            w_methods.push(bit_field.const_builder(&size));
+        }
 
         let w_type = bit_field.w_proxy_name();
         let getter = bit_field.snake_case_name();
@@ -401,6 +890,9 @@ fn write_register_code(r: &Register, bit_fields: &Vec<RegisterBitFieldAndEnum>)
         ))
     }
 
+    let display_impl = display_impl(r, &ident("W"));
+    let value_trait_impls = value_trait_impls(r, bit_fields, EnumMode::Write, options);
+
     quote! {
         #[doc = "Value read from the register"]
         pub struct W {
@@ -411,10 +903,151 @@ fn write_register_code(r: &Register, bit_fields: &Vec<RegisterBitFieldAndEnum>)
             #( #w_methods )*
         }
 
+        #display_impl
+        #value_trait_impls
+
         #( #w_items )*
     }
 }
 
+/// `Display` implementation for `R` or `W` which decodes field values
+/// to enum value names.
+fn display_impl(r: &Register, value_type: &Ident) -> TokenStream {
+    let register = r.name.as_str().to_pascal_case();
+
+    let fields = r.functions.iter().map(|bit_field| {
+        let range = &bit_field.range;
+        let (name, reserved) = match bit_field.name() {
+            Some(name) => (name.to_pascal_case(), false),
+            None => (format!("Reserved[{}]", range), true),
+        };
+
+        let mask = lit_int(range.max_value().unwrap());
+        let value = if range.lsb == 0 {
+            quote! { raw_bits & #mask }
+        } else {
+            let lsb = lit_int(range.lsb);
+            quote! { (raw_bits >> #lsb) & #mask }
+        };
+
+        let enum_values: Vec<&RegisterEnumValue> = r.enums.iter()
+            .filter(|e| !reserved && e.range == *range)
+            .flat_map(|e| e.values.iter())
+            .collect();
+
+        let value_name = if enum_values.is_empty() {
+            quote! { None }
+        } else {
+            let values = enum_values.iter().map(|v| lit_int(v.value));
+            let value_names = enum_values.iter().map(|v| v.name.as_str().to_pascal_case());
+            quote! {
+                match #value {
+                    #( #values => Some(#value_names), )*
+                    _ => None,
+                }
+            }
+        };
+
+        quote! {
+            DisplayField {
+                name: #name,
+                value: #value,
+                value_name: #value_name,
+                reserved: #reserved,
+            }
+        }
+    });
+
+    quote! {
+        impl core::fmt::Display for #value_type {
+            fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                let raw_bits = u64::from(self.raw_bits);
+                display_register(f, #register, raw_bits, &[ #( #fields ),* ])
+            }
+        }
+    }
+}
+
+/// Optional serde and defmt implementations for `R` or `W`.
+///
+/// Fields are (de)serialized by name. Fields with a complete enum use
+/// the enum variant name, other fields use `bool` or the register
+/// integer type. Reserved bit fields are not included.
+fn value_trait_impls(r: &Register, bit_fields: &[RegisterBitFieldAndEnum], mode: EnumMode, options: &RustOptions) -> TokenStream {
+    let size = ident(r.size_in_bits.rust_unsigned_integer());
+    let register = r.name.as_str().to_constant_case();
+    let value_type = match mode {
+        EnumMode::Read => ident("R"),
+        EnumMode::Write => ident("W"),
+    };
+
+    let fields: Vec<String> = bit_fields.iter().map(|b| b.snake_case_name_string()).collect();
+    let field_values: Vec<TokenStream> = bit_fields.iter().map(|b| b.field_value(&mode)).collect();
+    let (fields, field_values) = (&fields, &field_values);
+
+    let mut impls = vec![];
+
+    if let Some(feature) = &options.serde_feature {
+        let field_count = bit_fields.len();
+        let field_names: Vec<Ident> = bit_fields.iter().map(|b| b.snake_case_name()).collect();
+        let field_names = &field_names;
+        let field_types = bit_fields.iter().map(|b| b.field_value_type(&size, &mode));
+        let raw_bits = bit_fields.iter().map(|b| b.raw_bits_from_field_value(&b.snake_case_name(), &mode));
+
+        impls.push(quote! {
+            #[cfg(feature = #feature)]
+            impl serde::Serialize for #value_type {
+                fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    use serde::ser::SerializeStruct;
+                    let mut s = serializer.serialize_struct(#register, #field_count)?;
+                    #(
+                        s.serialize_field(#fields, &#field_values)?;
+                    )*
+                    s.end()
+                }
+            }
+
+            #[cfg(feature = #feature)]
+            impl <'de> serde::Deserialize<'de> for #value_type {
+                fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    #[derive(serde::Deserialize)]
+                    #[serde(rename = #register)]
+                    struct Fields {
+                        #( #field_names: #field_types, )*
+                    }
+
+                    let Fields { #( #field_names ),* } = Fields::deserialize(deserializer)?;
+
+                    Ok(#value_type {
+                        raw_bits: 0 #( | #raw_bits )*,
+                    })
+                }
+            }
+        });
+    }
+
+    if let Some(feature) = &options.defmt_feature {
+        let format_string = format!(
+            "{} {{{{ {} }}}}",
+            register,
+            fields.iter().map(|f| format!("{}: {{}}", f)).collect::<Vec<String>>().join(", "),
+        );
+
+        impls.push(quote! {
+            #[cfg(feature = #feature)]
+            impl defmt::Format for #value_type {
+                fn format(&self, f: defmt::Formatter) {
+                    defmt::write!(f, #format_string #( , #field_values )*)
+                }
+            }
+        });
+    }
+
+    quote! {
+        #( #impls )*
+    }
+}
+
 impl RegisterEnumValue {
     fn variant_rust_name(&self) -> Ident {
         ident(self.name.as_str().to_constant_case())
@@ -509,8 +1142,9 @@ pub enum EnumType {
 
 pub struct RegisterBitFieldAndEnum {
     /// Bit field which is not marked as reserved.
-    bit_field: RegisterFunction,
-    register_enum: Option<RegisterEnum>,
+    pub(super) bit_field: RegisterFunction,
+    pub(super) register_enum: Option<RegisterEnum>,
+    register_size: RegisterSize,
 }
 
 impl RegisterBitFieldAndEnum {
@@ -584,7 +1218,7 @@ impl RegisterBitFieldAndEnum {
         }
     }
 
-    fn enum_item(&self, register_size: &Ident, name: &Ident, mode: EnumMode, only_complete_enum: bool) -> TokenStream {
+    fn enum_item(&self, register_size: &Ident, name: &Ident, mode: EnumMode, only_complete_enum: bool, options: &RustOptions) -> TokenStream {
         let variants = self.variant_list(register_size, only_complete_enum);
         let repr = if self.enum_type() == EnumType::Complete {
             quote! { #[repr(#register_size)] }
@@ -607,11 +1241,21 @@ impl RegisterBitFieldAndEnum {
             quote! {}
         };
 
+        let serde_derive = options.serde_feature.as_ref().map(|feature| quote! {
+            #[cfg_attr(feature = #feature, derive(serde::Serialize, serde::Deserialize))]
+        });
+
+        let defmt_derive = options.defmt_feature.as_ref().map(|feature| quote! {
+            #[cfg_attr(feature = #feature, derive(defmt::Format))]
+        });
+
         quote! {
             #repr
             #[doc = #doc]
             #additional_documentation
             #[derive(Debug, Clone, Copy, PartialEq)]
+            #serde_derive
+            #defmt_derive
             pub enum #name {
                 #( #variants, )*
             }
@@ -629,7 +1273,13 @@ impl RegisterBitFieldAndEnum {
         }
     }
 
-    fn conversion_methods(&self, name: &Ident, register_size: &Ident, enum_type: EnumType, only_to_register_value: bool) -> Vec<TokenStream> {
+    fn conversion_methods(&self, name: &Ident, register_size: &Ident, enum_type: EnumType, only_to_register_value: bool, const_fn: bool) -> Vec<TokenStream> {
+        let constness = if const_fn {
+            quote! { const }
+        } else {
+            quote! {}
+        };
+
         let remove_additional_bits = quote! {
             let value = value & Self::_MASK;
         };
@@ -654,7 +1304,7 @@ impl RegisterBitFieldAndEnum {
 
                 r.push(quote! {
                     #[inline]
-                    pub fn to_register_value(&self) -> #register_size {
+                    pub #constness fn to_register_value(&self) -> #register_size {
                         let value = *self as #register_size;
                         #shift_bits_to_register_position
                         value
@@ -696,7 +1346,7 @@ impl RegisterBitFieldAndEnum {
             EnumType::ReservedBoolean => {
                 r.push(quote! {
                     #[inline]
-                    pub fn to_register_value(&self) -> #register_size {
+                    pub #constness fn to_register_value(&self) -> #register_size {
                         match *self {
                             #name::_Reserved(true) => Self::_MASK,
                             #name::_Reserved(false) => 0,
@@ -727,7 +1377,7 @@ impl RegisterBitFieldAndEnum {
             EnumType::ReservedNumber => {
                 r.push(quote! {
                     #[inline]
-                    pub fn to_register_value(&self) -> #register_size {
+                    pub #constness fn to_register_value(&self) -> #register_size {
                         let value = match *self {
                             #name::_Reserved(value) => value,
                         };
@@ -762,6 +1412,71 @@ impl RegisterBitFieldAndEnum {
         r
     }
 
+    /// Field value of `R` or `W` as field enum, `bool` or register integer type.
+    fn field_value(&self, mode: &EnumMode) -> TokenStream {
+        match (mode, self.enum_type()) {
+            (EnumMode::Read, enum_type) => {
+                let getter = self.snake_case_name();
+                match enum_type {
+                    EnumType::Complete => quote! { self.#getter() },
+                    EnumType::ReservedBoolean => quote! { self.#getter().bit() },
+                    EnumType::ReservedNumber => quote! { self.#getter().bits() },
+                }
+            }
+            (EnumMode::Write, EnumType::Complete) => {
+                let name = self.write_enum_name();
+                quote! { #name::from_register_value(self.raw_bits) }
+            }
+            (EnumMode::Write, EnumType::ReservedBoolean) => {
+                let proxy = self.w_proxy_name();
+                quote! { (self.raw_bits & #proxy::_MASK != 0) }
+            }
+            (EnumMode::Write, EnumType::ReservedNumber) => {
+                let proxy = self.w_proxy_name();
+                quote! { ((self.raw_bits & #proxy::_MASK) >> #proxy::_OFFSET) }
+            }
+        }
+    }
+
+    /// Type of the value returned from `field_value`.
+    fn field_value_type(&self, register_size: &Ident, mode: &EnumMode) -> TokenStream {
+        match (mode, self.enum_type()) {
+            (EnumMode::Read, EnumType::Complete) => {
+                let name = self.read_enum_name();
+                quote! { #name }
+            }
+            (EnumMode::Write, EnumType::Complete) => {
+                let name = self.write_enum_name();
+                quote! { #name }
+            }
+            (_, EnumType::ReservedBoolean) => quote! { bool },
+            (_, EnumType::ReservedNumber) => quote! { #register_size },
+        }
+    }
+
+    /// Converts field value in variable `value` to raw register bits.
+    fn raw_bits_from_field_value(&self, value: &Ident, mode: &EnumMode) -> TokenStream {
+        match (mode, self.enum_type()) {
+            (_, EnumType::Complete) => quote! { #value.to_register_value() },
+            (EnumMode::Read, EnumType::ReservedBoolean) => {
+                let name = self.read_enum_name();
+                quote! { #name::_Reserved(#value).to_register_value() }
+            }
+            (EnumMode::Read, EnumType::ReservedNumber) => {
+                let name = self.read_enum_name();
+                quote! { (#name::_Reserved(#value).to_register_value() & #name::_MASK) }
+            }
+            (EnumMode::Write, EnumType::ReservedBoolean) => {
+                let proxy = self.w_proxy_name();
+                quote! { (if #value { #proxy::_MASK } else { 0 }) }
+            }
+            (EnumMode::Write, EnumType::ReservedNumber) => {
+                let proxy = self.w_proxy_name();
+                quote! { ((#value << #proxy::_OFFSET) & #proxy::_MASK) }
+            }
+        }
+    }
+
     fn enum_type(&self) -> EnumType {
         match (self.is_boolean(), &self.register_enum) {
             (_, Some(e)) if e.all_possible_values_are_defined => EnumType::Complete,
@@ -780,7 +1495,7 @@ impl RegisterBitFieldAndEnum {
             methods.extend(e.is_variant_method_list(&name, enum_type));
         }
 
-        methods.extend(self.conversion_methods(&name, &register_size, enum_type, false));
+        methods.extend(self.conversion_methods(&name, register_size, enum_type, false, false));
 
         if self.is_boolean() {
             methods.push(quote! {
@@ -806,9 +1521,9 @@ impl RegisterBitFieldAndEnum {
     }
 
 
-    fn read_code(&self, register_size: &Ident) -> TokenStream {
+    fn read_code(&self, register_size: &Ident, options: &RustOptions) -> TokenStream {
 
-        let e = self.enum_item(register_size, &self.read_enum_name(), EnumMode::Read, false);
+        let e = self.enum_item(register_size, &self.read_enum_name(), EnumMode::Read, false, options);
         let e_impl = self.read_enum_impl(register_size);
 
         quote! {
@@ -817,7 +1532,7 @@ impl RegisterBitFieldAndEnum {
         }
     }
 
-    fn w_proxy_methods(&self, register_size: &Ident) -> Vec<TokenStream> {
+    fn w_proxy_methods(&self, register_size: &Ident, options: &RustOptions) -> Vec<TokenStream> {
         let w_enum_name = self.write_enum_name();
 
         let mut r = vec![
@@ -852,10 +1567,49 @@ impl RegisterBitFieldAndEnum {
                 }
             })
         } else {
+            let field = self.snake_case_name_string();
+            let bit_count = self.bit_field.range.bit_count().get();
+            let max_value = lit_int(self.bit_field.range.max_value().unwrap());
+            // Values of a bit field which is as wide as the register can't overflow.
+            let can_overflow = bit_count < self.register_size as u32;
+
+            let debug_assert = if options.debug_assert_bits && can_overflow {
+                quote! {
+                    debug_assert!(value <= #max_value, "value {:#x} does not fit to {} bit field `{}`", value, #bit_count, #field);
+                }
+            } else {
+                quote! {}
+            };
+
+            let try_bits = if can_overflow {
+                quote! {
+                    if value > #max_value {
+                        return Err(FieldOverflow {
+                            field: #field,
+                            bit_count: #bit_count,
+                            value: u64::from(value),
+                        });
+                    }
+
+                    Ok(self.bits(value))
+                }
+            } else {
+                quote! { Ok(self.bits(value)) }
+            };
+
+            r.push(quote! {
+                #[doc = "Writes raw bits to the field. Returns error if `value` does not fit to the field."]
+                #[inline]
+                pub fn try_bits(self, value: #register_size) -> Result<&'a mut W, FieldOverflow> {
+                    #try_bits
+                }
+            });
+
             r.push(quote! {
                 #[doc = "Writes raw bits to the field"]
                 #[inline]
                 pub fn bits(self, value: #register_size) -> &'a mut W {
+                    #debug_assert
                     // Convert bit field value to register value.
                     let value = value << Self::_OFFSET;
                     // Clear other bits which are not part of this bit field.
@@ -926,9 +1680,54 @@ impl RegisterBitFieldAndEnum {
         r
     }
 
-    fn w_proxy(&self, register_size: &Ident) -> TokenStream {
+    /// Const method `with_<field>` of `W` which returns `W` with the field updated.
+    fn const_builder(&self, register_size: &Ident) -> TokenStream {
+        let name = ident(format!("with_{}", self.snake_case_name_string()));
+        let proxy = self.w_proxy_name();
+        let doc = format!("Returns the value with field `{}` set to `value`", self.snake_case_name_string());
+
+        let mut value_check = quote! {};
+        let mut panic_doc = quote! {};
+        let (value_type, raw_bits) = match self.enum_type() {
+            EnumType::Complete => {
+                let w_enum_name = self.write_enum_name();
+                (quote! { #w_enum_name }, quote! { (self.raw_bits & !#proxy::_MASK) | value.to_register_value() })
+            }
+            EnumType::ReservedBoolean => {
+                (quote! { bool }, quote! { if value { self.raw_bits | #proxy::_MASK } else { self.raw_bits & !#proxy::_MASK } })
+            }
+            EnumType::ReservedNumber => {
+                let bit_count = self.bit_field.range.bit_count().get();
+                // Values of a bit field which is as wide as the register can't overflow.
+                if bit_count < self.register_size as u32 {
+                    let max_value = lit_int(self.bit_field.range.max_value().unwrap());
+                    let message = format!("value does not fit to {} bit field `{}`", bit_count, self.snake_case_name_string());
+                    panic_doc = quote! {
+                        #[doc = ""]
+                        #[doc = "Panics if `value` does not fit to the field. In const context the panic is a compile error."]
+                    };
+                    value_check = quote! {
+                        assert!(value <= #max_value, #message);
+                    };
+                }
+                (quote! { #register_size }, quote! { (self.raw_bits & !#proxy::_MASK) | ((value << #proxy::_OFFSET) & #proxy::_MASK) })
+            }
+        };
+
+        quote! {
+            #[doc = #doc]
+            #panic_doc
+            #[inline]
+            pub const fn #name(self, value: #value_type) -> Self {
+                #value_check
+                W { raw_bits: #raw_bits }
+            }
+        }
+    }
+
+    fn w_proxy(&self, register_size: &Ident, options: &RustOptions) -> TokenStream {
         let name = self.w_proxy_name();
-        let methods = self.w_proxy_methods(register_size);
+        let methods = self.w_proxy_methods(register_size, options);
 
         quote! {
             #[doc = "Proxy"]
@@ -942,13 +1741,15 @@ impl RegisterBitFieldAndEnum {
         }
     }
 
-    fn write_code(&self, register_size: &Ident) -> TokenStream {
+    fn write_code(&self, register_size: &Ident, options: &RustOptions) -> TokenStream {
         let enum_type = self.enum_type();
 
         let w_enum = if enum_type == EnumType::Complete {
-            let e = self.enum_item(register_size, &self.write_enum_name(), EnumMode::Write, true);
+            let e = self.enum_item(register_size, &self.write_enum_name(), EnumMode::Write, true, options);
             let name = self.write_enum_name();
-            let e_methods = self.conversion_methods(&name, &register_size, enum_type, true);
+            // Serde and defmt implementations of W read field values from the register value.
+            let only_to_register_value = options.serde_feature.is_none() && options.defmt_feature.is_none();
+            let e_methods = self.conversion_methods(&name, register_size, enum_type, only_to_register_value, options.const_fn);
 
             quote! {
                 #e
@@ -961,7 +1762,7 @@ impl RegisterBitFieldAndEnum {
             quote! {}
         };
 
-        let w_proxy = self.w_proxy(register_size);
+        let w_proxy = self.w_proxy(register_size, options);
 
         quote! {
             #w_enum
@@ -970,7 +1771,7 @@ impl RegisterBitFieldAndEnum {
     }
 }
 
-fn bit_fields_and_enums(r: &Register) -> Vec<RegisterBitFieldAndEnum> {
+pub(super) fn bit_fields_and_enums(r: &Register) -> Vec<RegisterBitFieldAndEnum> {
     r.functions.iter().filter(|bit_field| bit_field.status.is_normal()).map(|bit_field| {
         let mut register_enum = None;
         for e in r.enums.iter() {
@@ -983,6 +1784,7 @@ fn bit_fields_and_enums(r: &Register) -> Vec<RegisterBitFieldAndEnum> {
         RegisterBitFieldAndEnum {
             bit_field: bit_field.clone(),
             register_enum,
+            register_size: r.size_in_bits,
         }
     }).collect()
 }
@@ -3,13 +3,26 @@ use quote::quote;
 
 use proc_macro2::TokenStream;
 
-use crate::logic::validation::register_description::RegisterDescription;
+use crate::{
+    config::RustOptions,
+    logic::validation::{
+        register::RegisterLocation,
+        register_description::RegisterDescription,
+    },
+};
 
-use super::ident;
+use super::{ident, lit_int};
 
-pub fn register_trait_module(rd: &RegisterDescription) -> TokenStream {
+pub fn register_trait_module(rd: &RegisterDescription, options: &RustOptions) -> TokenStream {
     let index_type = ident(rd.index_size.rust_unsigned_integer());
     let address_type = rd.address_size.rust_type();
+
+    let fallible_traits = if options.fallible {
+        fallible_register_traits(rd)
+    } else {
+        quote! {}
+    };
+
     quote! {
         pub mod register_trait {
             pub trait LocationIndexR {
@@ -65,6 +78,196 @@ pub fn register_trait_module(rd: &RegisterDescription) -> TokenStream {
             pub trait InGroup {
                 type Group: RegisterGroup;
             }
+
+            #[doc = "Error for a value which does not fit to the bit field"]
+            #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+            pub struct FieldOverflow {
+                pub field: &'static str,
+                pub bit_count: u32,
+                pub value: u64,
+            }
+
+            impl core::fmt::Display for FieldOverflow {
+                fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                    write!(f, "value {:#x} does not fit to {} bit field `{}`", self.value, self.bit_count, self.field)
+                }
+            }
+
+            #[doc = "Bit field of a register value for `Display` implementations"]
+            pub struct DisplayField {
+                pub name: &'static str,
+                pub value: u64,
+                #[doc = "Name of the enum value if the value matches one"]
+                pub value_name: Option<&'static str>,
+                #[doc = "Reserved fields are displayed only if the value is nonzero"]
+                pub reserved: bool,
+            }
+
+            #[doc = "Formats register value like `Name = 0x67 [Field=Value, Other=0x1]`"]
+            pub fn display_register(f: &mut core::fmt::Formatter, name: &str, raw_bits: u64, fields: &[DisplayField]) -> core::fmt::Result {
+                write!(f, "{} = {:#x} [", name, raw_bits)?;
+                let fields = fields.iter().filter(|field| !field.reserved || field.value != 0);
+                for (i, field) in fields.enumerate() {
+                    if i != 0 {
+                        f.write_str(", ")?;
+                    }
+
+                    match field.value_name {
+                        Some(value_name) => write!(f, "{}={}", field.name, value_name)?,
+                        None => write!(f, "{}={:#x}", field.name, field.value)?,
+                    }
+                }
+                f.write_str("]")
+            }
+
+            #fallible_traits
+        }
+    }
+}
+
+fn fallible_register_traits(rd: &RegisterDescription) -> TokenStream {
+    let index_type = ident(rd.index_size.rust_unsigned_integer());
+    let address_type = rd.address_size.rust_type();
+
+    let traits = [
+        ("RegisterIndexIoR", "RegisterIndexIoW", "index", &index_type),
+        ("RegisterAbsIoR", "RegisterAbsIoW", "abs_address", &address_type),
+        ("RegisterRelIoR", "RegisterRelIoW", "rel_address", &address_type),
+    ];
+
+    let traits = traits.iter().map(|(read_trait, write_trait, location, location_type)| {
+        let read_trait = ident(read_trait);
+        let write_trait = ident(write_trait);
+        let try_read_trait = ident(format!("Try{}", read_trait));
+        let try_write_trait = ident(format!("Try{}", write_trait));
+        let location = ident(location);
+
+        quote! {
+            pub trait #try_read_trait<T: RegisterGroup, U: Sized>: TryRegisterIo {
+                fn try_read(&mut self, #location: #location_type) -> Result<U, Self::Error>;
+            }
+
+            pub trait #try_write_trait<T: RegisterGroup, U: Sized>: TryRegisterIo {
+                fn try_write(&mut self, #location: #location_type, value: U) -> Result<(), Self::Error>;
+            }
+
+            impl <T: RegisterGroup, U: Sized, I: #read_trait<T, U>> #try_read_trait<T, U> for InfallibleIo<I> {
+                #[inline]
+                fn try_read(&mut self, #location: #location_type) -> Result<U, Self::Error> {
+                    Ok(self.0.read(#location))
+                }
+            }
+
+            impl <T: RegisterGroup, U: Sized, I: #write_trait<T, U>> #try_write_trait<T, U> for InfallibleIo<I> {
+                #[inline]
+                fn try_write(&mut self, #location: #location_type, value: U) -> Result<(), Self::Error> {
+                    self.0.write(#location, value);
+                    Ok(())
+                }
+            }
+        }
+    });
+
+    quote! {
+        #[doc = "Error type of fallible register IO"]
+        pub trait TryRegisterIo {
+            type Error;
+        }
+
+        pub type TryIoError<T> = <T as TryRegisterIo>::Error;
+
+        #[doc = "Adapter for using infallible register IO with fallible register IO traits"]
+        pub struct InfallibleIo<T>(pub T);
+
+        impl <T> TryRegisterIo for InfallibleIo<T> {
+            type Error = core::convert::Infallible;
+        }
+
+        #( #traits )*
+    }
+}
+
+/// Async versions of the register IO traits. Register location traits
+/// and `RegisterGroup` are shared with the `register_trait` module.
+pub fn register_trait_async_module(rd: &RegisterDescription) -> TokenStream {
+    let index_type = ident(rd.index_size.rust_unsigned_integer());
+    let address_type = rd.address_size.rust_type();
+
+    quote! {
+        #[allow(async_fn_in_trait)]
+        pub mod register_trait_async {
+            use super::register_trait::RegisterGroup;
+
+            pub trait AsyncRegisterIndexIoR<T: RegisterGroup, U: Sized> {
+                async fn read(&mut self, index: #index_type) -> U;
+            }
+
+            pub trait AsyncRegisterIndexIoW<T: RegisterGroup, U: Sized> {
+                async fn write(&mut self, index: #index_type, value: U);
+            }
+
+            pub trait AsyncRegisterAbsIoR<T: RegisterGroup, U: Sized> {
+                async fn read(&mut self, abs_address: #address_type) -> U;
+            }
+
+            pub trait AsyncRegisterAbsIoW<T: RegisterGroup, U: Sized> {
+                async fn write(&mut self, abs_address: #address_type, value: U);
+            }
+
+            pub trait AsyncRegisterRelIoR<T: RegisterGroup, U: Sized> {
+                async fn read(&mut self, rel_address: #address_type) -> U;
+            }
+
+            pub trait AsyncRegisterRelIoW<T: RegisterGroup, U: Sized> {
+                async fn write(&mut self, rel_address: #address_type, value: U);
+            }
+        }
+    }
+}
+
+/// Types for describing register accesses. Used by generated IO implementations.
+pub fn register_access_module() -> TokenStream {
+    quote! {
+        pub mod register_access {
+            #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+            pub enum Location {
+                Index(u64),
+                Absolute(u64),
+                Relative(u64),
+            }
+
+            #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+            pub enum Op {
+                Read,
+                Write,
+            }
+
+            impl core::fmt::Display for Op {
+                fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                    match self {
+                        Op::Read => f.write_str("read"),
+                        Op::Write => f.write_str("write"),
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Expression of type `register_access::Location`.
+pub fn access_location(location: RegisterLocation) -> TokenStream {
+    match location {
+        RegisterLocation::Index(value) => {
+            let value = lit_int(value);
+            quote! { Location::Index(#value) }
+        }
+        RegisterLocation::Absolute(value) => {
+            let value = lit_int(value);
+            quote! { Location::Absolute(#value) }
+        }
+        RegisterLocation::Relative(value) => {
+            let value = lit_int(value);
+            quote! { Location::Relative(#value) }
         }
     }
 }
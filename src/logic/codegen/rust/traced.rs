@@ -0,0 +1,314 @@
+
+use quote::quote;
+
+use syn::Ident;
+use proc_macro2::TokenStream;
+use inflections::Inflect;
+
+use crate::logic::validation::{
+    register::{
+        Register,
+        AccessMode,
+    },
+    register_description::RegisterDescription,
+};
+
+use super::{
+    RustOptions,
+    ident,
+    lit_int,
+    register::bit_fields_and_enums,
+    register_trait::access_location,
+};
+
+pub fn traced_module(rd: &RegisterDescription, options: &RustOptions) -> TokenStream {
+    let index_type = ident(rd.index_size.rust_unsigned_integer());
+    let address_type = rd.address_size.rust_type();
+
+    let io_traits = [
+        ("RegisterIndexIoR", "RegisterIndexIoW", "Index", &index_type),
+        ("RegisterAbsIoR", "RegisterAbsIoW", "Absolute", &address_type),
+        ("RegisterRelIoR", "RegisterRelIoW", "Relative", &address_type),
+    ];
+
+    let io_impls = io_traits.iter().map(|(read_trait, write_trait, location, location_type)| {
+        let location = ident(location);
+
+        let io_impl = |read_trait: Ident, write_trait: Ident, asyncness: TokenStream, await_io: TokenStream| quote! {
+            impl <G: TracedGroup, U: Copy + Into<u64>, T: #read_trait<G, U>, F: FnMut(&TraceEvent)> #read_trait<G, U> for Traced<T, F> {
+                #asyncness fn read(&mut self, location: #location_type) -> U {
+                    let value = self.io.read(location)#await_io;
+                    self.trace::<G>(Op::Read, Location::#location(location as u64), value.into());
+                    value
+                }
+            }
+
+            impl <G: TracedGroup, U: Copy + Into<u64>, T: #write_trait<G, U>, F: FnMut(&TraceEvent)> #write_trait<G, U> for Traced<T, F> {
+                #asyncness fn write(&mut self, location: #location_type, value: U) {
+                    self.io.write(location, value)#await_io;
+                    self.trace::<G>(Op::Write, Location::#location(location as u64), value.into());
+                }
+            }
+        };
+
+        let sync_impl = io_impl(ident(read_trait), ident(write_trait), quote! {}, quote! {});
+
+        let async_impl = if options.async_io {
+            io_impl(ident(format!("Async{}", read_trait)), ident(format!("Async{}", write_trait)), quote! { async }, quote! { .await })
+        } else {
+            quote! {}
+        };
+
+        // Only successful accesses are traced.
+        let fallible_impl = if options.fallible {
+            let try_read_trait = ident(format!("Try{}", read_trait));
+            let try_write_trait = ident(format!("Try{}", write_trait));
+            quote! {
+                impl <G: TracedGroup, U: Copy + Into<u64>, T: #try_read_trait<G, U>, F: FnMut(&TraceEvent)> #try_read_trait<G, U> for Traced<T, F> {
+                    fn try_read(&mut self, location: #location_type) -> Result<U, Self::Error> {
+                        let value = self.io.try_read(location)?;
+                        self.trace::<G>(Op::Read, Location::#location(location as u64), value.into());
+                        Ok(value)
+                    }
+                }
+
+                impl <G: TracedGroup, U: Copy + Into<u64>, T: #try_write_trait<G, U>, F: FnMut(&TraceEvent)> #try_write_trait<G, U> for Traced<T, F> {
+                    fn try_write(&mut self, location: #location_type, value: U) -> Result<(), Self::Error> {
+                        self.io.try_write(location, value)?;
+                        self.trace::<G>(Op::Write, Location::#location(location as u64), value.into());
+                        Ok(())
+                    }
+                }
+            }
+        } else {
+            quote! {}
+        };
+
+        quote! {
+            #sync_impl
+            #async_impl
+            #fallible_impl
+        }
+    });
+
+    let async_traits = if options.async_io {
+        quote! { use super::register_trait_async::*; }
+    } else {
+        quote! {}
+    };
+
+    let fallible_io = if options.fallible {
+        quote! {
+            impl <T: TryRegisterIo, F: FnMut(&TraceEvent)> TryRegisterIo for Traced<T, F> {
+                type Error = T::Error;
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let trace_event = quote! {
+        #[doc = "Register access"]
+        #[derive(Debug, Clone, Copy)]
+        pub struct TraceEvent {
+            pub op: Op,
+            pub location: Location,
+            pub value: u64,
+            #[doc = "Register description metadata if location matched some register of the group"]
+            pub register: Option<&'static RegisterInfo>,
+        }
+
+        impl TraceEvent {
+            #[doc = "Field values of the register. Reserved fields are not included."]
+            pub fn fields(&self) -> impl Iterator<Item=DecodedField> + '_ {
+                let value = self.value;
+                self.register.into_iter().flat_map(|r| r.fields.iter()).map(move |field| field.decode(value))
+            }
+        }
+
+        impl core::fmt::Display for TraceEvent {
+            fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+                match self.register {
+                    Some(r) if r.group.is_empty() => write!(f, "{} {} = {:#x}", self.op, r.name, self.value)?,
+                    Some(r) => write!(f, "{} {}.{} = {:#x}", self.op, r.group, r.name, self.value)?,
+                    None => return write!(f, "{} {:?} = {:#x}", self.op, self.location, self.value),
+                }
+
+                f.write_str(" [")?;
+                for (i, field) in self.fields().enumerate() {
+                    if i != 0 {
+                        f.write_str(", ")?;
+                    }
+
+                    match field.value_name {
+                        Some(name) => write!(f, "{}={}", field.name, name)?,
+                        None => write!(f, "{}={:#x}", field.name, field.value)?,
+                    }
+                }
+                f.write_str("]")
+            }
+        }
+    };
+
+    quote! {
+        pub mod traced {
+            use super::register_trait::*;
+            #async_traits
+            pub use super::register_access::{Location, Op};
+
+            #[doc = "Register metadata from the register description"]
+            #[derive(Debug)]
+            pub struct RegisterInfo {
+                pub group: &'static str,
+                pub name: &'static str,
+                pub fields: &'static [FieldInfo],
+            }
+
+            #[derive(Debug)]
+            pub struct FieldInfo {
+                pub name: &'static str,
+                pub msb: u16,
+                pub lsb: u16,
+                #[doc = "Enum values of the field"]
+                pub values: &'static [(u64, &'static str)],
+            }
+
+            impl FieldInfo {
+                pub fn decode(&'static self, register_value: u64) -> DecodedField {
+                    let bit_count = (self.msb - self.lsb + 1) as u32;
+                    let mask = u64::max_value().checked_shr(64 - bit_count).unwrap_or(0);
+                    let value = (register_value >> self.lsb) & mask;
+                    let value_name = self.values.iter().find(|(v, _)| *v == value).map(|(_, name)| *name);
+
+                    DecodedField {
+                        name: self.name,
+                        value,
+                        value_name,
+                    }
+                }
+            }
+
+            #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+            pub struct DecodedField {
+                pub name: &'static str,
+                pub value: u64,
+                #[doc = "Name of the enum value if the value matches one"]
+                pub value_name: Option<&'static str>,
+            }
+
+            #[doc = "Register group which can be used with `Traced`"]
+            pub trait TracedGroup: RegisterGroup {
+                fn register_info(op: Op, location: Location) -> Option<&'static RegisterInfo>;
+            }
+
+            #trace_event
+
+            #[doc = "IO adapter which calls a callback after every register access"]
+            pub struct Traced<T, F: FnMut(&TraceEvent)> {
+                io: T,
+                callback: F,
+            }
+
+            impl <T, F: FnMut(&TraceEvent)> Traced<T, F> {
+                pub fn new(io: T, callback: F) -> Self {
+                    Self { io, callback }
+                }
+
+                pub fn io(&self) -> &T {
+                    &self.io
+                }
+
+                pub fn into_io(self) -> T {
+                    self.io
+                }
+
+                fn trace<G: TracedGroup>(&mut self, op: Op, location: Location, value: u64) {
+                    let event = TraceEvent {
+                        op,
+                        location,
+                        value,
+                        register: G::register_info(op, location),
+                    };
+                    (self.callback)(&event);
+                }
+            }
+
+            #fallible_io
+            #( #io_impls )*
+        }
+    }
+}
+
+/// Register metadata uses the names of the register description, like
+/// `MockIo` does.
+fn register_info(r: &Register, group_name: &str) -> TokenStream {
+    let name = r.name.as_str();
+
+    let fields = bit_fields_and_enums(r).into_iter().map(|f| {
+        let field_name = f.bit_field.name().unwrap().to_string();
+        let msb = lit_int(f.bit_field.range.msb);
+        let lsb = lit_int(f.bit_field.range.lsb);
+
+        let values = f.register_enum.iter()
+            .flat_map(|e| e.values.iter())
+            .map(|v| {
+                let value = lit_int(v.value);
+                let value_name = v.name.as_str();
+                quote! { (#value, #value_name) }
+            });
+
+        quote! {
+            FieldInfo {
+                name: #field_name,
+                msb: #msb,
+                lsb: #lsb,
+                values: &[ #( #values ),* ],
+            }
+        }
+    });
+
+    quote! {
+        RegisterInfo {
+            group: #group_name,
+            name: #name,
+            fields: &[ #( #fields ),* ],
+        }
+    }
+}
+
+pub fn traced_group_impl(registers: &[Register], group_type: &Ident, group_name: &str) -> TokenStream {
+    let mut statics = vec![];
+    let mut info_arms = vec![];
+
+    for r in registers {
+        let info = register_info(r, group_name);
+        let static_name = ident(format!("{}_INFO", r.name.as_str().to_constant_case()));
+        statics.push(quote! { static #static_name: RegisterInfo = #info; });
+
+        if let AccessMode::Read | AccessMode::ReadWrite = r.access_mode {
+            let location = access_location(r.read_location);
+            info_arms.push(quote! { (Op::Read, #location) => Some(&#static_name), });
+        }
+
+        if let AccessMode::Write | AccessMode::ReadWrite = r.access_mode {
+            let location = access_location(r.write_location);
+            info_arms.push(quote! { (Op::Write, #location) => Some(&#static_name), });
+        }
+    }
+
+    quote! {
+        impl super::traced::TracedGroup for #group_type {
+            #[allow(unreachable_patterns)]
+            fn register_info(op: super::traced::Op, location: super::traced::Location) -> Option<&'static super::traced::RegisterInfo> {
+                use super::traced::{Op, Location, RegisterInfo, FieldInfo};
+
+                #( #statics )*
+
+                match (op, location) {
+                    #( #info_arms )*
+                    _ => None,
+                }
+            }
+        }
+    }
+}
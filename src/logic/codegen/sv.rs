@@ -0,0 +1,376 @@
+//! SystemVerilog package code generation.
+//!
+//! Package `<name>_pkg` contains register location localparams and a packed
+//! struct for every register. The optional UVM register model is generated
+//! to package `<name>_uvm_pkg`.
+
+use std::{
+    cmp::Reverse,
+    fmt::Write as FmtWrite,
+    io::Write,
+    fs,
+};
+
+use inflections::Inflect;
+
+use crate::{
+    config::SvOptions,
+    logic::validation::{
+        ParsedFile,
+        register::{
+            Register,
+            RegisterFunction,
+            RegisterEnum,
+            RegisterLocation,
+            RegisterSize,
+            AccessMode,
+        },
+        register_description::AddressSize,
+    },
+};
+
+use super::c::{
+    location_macros,
+    register_prefix,
+};
+
+/// SystemVerilog keywords and `uvm_reg` and `uvm_reg_block` method names
+/// which are likely to be used as field or register names.
+const RESERVED_NAMES: &[&str] = &[
+    "always", "assign", "begin", "bit", "buf", "byte", "case", "class",
+    "clocking", "config", "const", "default", "disable", "do", "edge", "else",
+    "end", "enum", "event", "final", "for", "force", "fork", "function",
+    "generate", "if", "initial", "inout", "input", "int", "integer", "interface",
+    "join", "local", "logic", "module", "new", "null", "output", "package",
+    "parameter", "priority", "program", "property", "rand", "real", "reg",
+    "release", "repeat", "return", "sequence", "signed", "static", "string",
+    "struct", "super", "table", "task", "this", "time", "type", "union",
+    "unique", "unsigned", "virtual", "void", "wait", "while", "wire",
+    "build", "configure", "get", "mirror", "peek", "poke", "predict", "read",
+    "reset", "set", "update", "write",
+];
+
+pub fn parsed_file_to_sv(parsed_file: &ParsedFile, output: &str, options: &SvOptions) {
+    let mut code = parsed_file_to_sv_package(parsed_file);
+
+    if options.uvm {
+        code.push_str(&parsed_file_to_uvm_package(parsed_file));
+    }
+
+    let mut f = fs::File::create(output).unwrap();
+    f.write_all(code.as_bytes()).unwrap();
+}
+
+fn parsed_file_to_sv_package(parsed_file: &ParsedFile) -> String {
+    let rd = &parsed_file.description;
+    let mut output = String::new();
+
+    let index_width = rd.index_size as usize;
+    let address_width = match rd.address_size {
+        AddressSize::Pointer => 64,
+        AddressSize::RegisterSize(size) => size as usize,
+    };
+
+    writeln!(output, "// Generated from register description `{}`", comment(rd.name.as_str())).unwrap();
+    if let Some(description) = &rd.description {
+        writeln!(output, "// {}", comment(description)).unwrap();
+    }
+    writeln!(output).unwrap();
+    writeln!(output, "package {}_pkg;", rd.name.as_str().to_snake_case()).unwrap();
+
+    for (group, registers) in parsed_file.register_groups() {
+        for r in registers {
+            let prefix = register_prefix(group, r);
+            let type_prefix = prefix.to_lowercase();
+
+            writeln!(output).unwrap();
+            writeln!(output, "  // {}", register_comment(r)).unwrap();
+
+            for (name, value) in location_macros(r) {
+                let width = if name.starts_with("INDEX") { index_width } else { address_width };
+                writeln!(output, "  localparam logic [{}:0] {}_{} = {}'h{:x};", width - 1, prefix, name, width, value).unwrap();
+            }
+
+            let mut functions: Vec<&RegisterFunction> = r.functions.iter().collect();
+            functions.sort_by_key(|f| Reverse(f.range.msb));
+
+            for f in functions.iter().filter(|f| f.status.is_normal()) {
+                if let Some(e) = r.enums.iter().find(|e| e.range == f.range) {
+                    enum_to_sv(&mut output, &prefix, f, e);
+                }
+            }
+
+            writeln!(output).unwrap();
+            writeln!(output, "  typedef struct packed {{").unwrap();
+            for f in &functions {
+                let field_type = if r.enums.iter().any(|e| e.range == f.range) && f.status.is_normal() {
+                    enum_type_name(&prefix, f)
+                } else {
+                    logic_type(f)
+                };
+
+                let bits = if f.range.bit_count().get() == 1 { "Bit" } else { "Bits" };
+
+                match f.description() {
+                    Some(description) => writeln!(output, "    {} {}; // {} {} - {}", field_type, struct_member_name(f), bits, f.range, comment(description)).unwrap(),
+                    None => writeln!(output, "    {} {}; // {} {}", field_type, struct_member_name(f), bits, f.range).unwrap(),
+                }
+            }
+            writeln!(output, "  }} {}_t;", type_prefix).unwrap();
+        }
+    }
+
+    writeln!(output).unwrap();
+    writeln!(output, "endpackage").unwrap();
+
+    output
+}
+
+fn enum_to_sv(output: &mut String, prefix: &str, f: &RegisterFunction, e: &RegisterEnum) {
+    let field_prefix = format!("{}_{}", prefix, f.name().unwrap().to_constant_case());
+    let bit_count = f.range.bit_count().get();
+
+    writeln!(output).unwrap();
+    if let Some(description) = &e.description {
+        writeln!(output, "  // {}", comment(description)).unwrap();
+    }
+    writeln!(output, "  typedef enum {} {{", logic_type(f)).unwrap();
+    for (i, v) in e.values.iter().enumerate() {
+        let separator = if i + 1 == e.values.len() { "" } else { "," };
+        let name = format!("{}_{}", field_prefix, v.name.as_str().to_constant_case());
+        match &v.description {
+            Some(description) => writeln!(output, "    {} = {}'d{}{} // {}", name, bit_count, v.value, separator, comment(description)).unwrap(),
+            None => writeln!(output, "    {} = {}'d{}{}", name, bit_count, v.value, separator).unwrap(),
+        }
+    }
+    writeln!(output, "  }} {};", enum_type_name(prefix, f)).unwrap();
+}
+
+/// UVM register view. Registers which have different read and write
+/// locations are modeled as separate read-only and write-only registers.
+struct UvmRegister<'a> {
+    register: &'a Register,
+    /// Instance name in the register block.
+    name: String,
+    class_name: String,
+    access: &'static str,
+    location: RegisterLocation,
+}
+
+impl <'a> UvmRegister<'a> {
+    fn from_register(group: Option<&str>, r: &'a Register) -> Vec<Self> {
+        let name = sv_name(r.name.as_str());
+        let class_name = format!("{}_reg", register_prefix(group, r).to_lowercase());
+        let view = |name: String, class_name: String, access, location| {
+            UvmRegister { register: r, name, class_name, access, location }
+        };
+
+        match r.access_mode {
+            AccessMode::Read => vec![view(name, class_name, "RO", r.read_location)],
+            AccessMode::Write => vec![view(name, class_name, "WO", r.write_location)],
+            AccessMode::ReadWrite if r.read_location == r.write_location => {
+                vec![view(name, class_name, "RW", r.read_location)]
+            }
+            AccessMode::ReadWrite => vec![
+                view(format!("{}_r", name), format!("{}_r", class_name), "RO", r.read_location),
+                view(format!("{}_w", name), format!("{}_w", class_name), "WO", r.write_location),
+            ],
+        }
+    }
+}
+
+fn parsed_file_to_uvm_package(parsed_file: &ParsedFile) -> String {
+    let rd = &parsed_file.description;
+    let mut output = String::new();
+
+    writeln!(output).unwrap();
+    writeln!(output, "package {}_uvm_pkg;", rd.name.as_str().to_snake_case()).unwrap();
+    writeln!(output).unwrap();
+    writeln!(output, "  import uvm_pkg::*;").unwrap();
+    writeln!(output, "  `include \"uvm_macros.svh\"").unwrap();
+
+    for (group, registers) in parsed_file.register_groups() {
+        let uvm_registers: Vec<UvmRegister> = registers.iter()
+            .flat_map(|r| UvmRegister::from_register(group, r))
+            .collect();
+
+        for r in &uvm_registers {
+            uvm_register_class(&mut output, r);
+        }
+
+        let block_name = format!("{}_block", group.unwrap_or_else(|| rd.name.as_str()).to_snake_case());
+        uvm_block_class(&mut output, &block_name, &uvm_registers);
+    }
+
+    writeln!(output).unwrap();
+    writeln!(output, "endpackage").unwrap();
+
+    output
+}
+
+fn uvm_register_class(output: &mut String, r: &UvmRegister) {
+    let fields: Vec<&RegisterFunction> = r.register.functions.iter().filter(|f| f.status.is_normal()).collect();
+    let class_name = &r.class_name;
+
+    writeln!(output).unwrap();
+    writeln!(output, "  // {}", register_comment(r.register)).unwrap();
+    writeln!(output, "  class {} extends uvm_reg;", class_name).unwrap();
+    writeln!(output, "    `uvm_object_utils({})", class_name).unwrap();
+    writeln!(output).unwrap();
+    for f in &fields {
+        writeln!(output, "    rand uvm_reg_field {};", field_name(f)).unwrap();
+    }
+    writeln!(output).unwrap();
+    writeln!(output, "    function new(string name = \"{}\");", class_name).unwrap();
+    writeln!(output, "      super.new(name, {}, UVM_NO_COVERAGE);", r.register.size_in_bits).unwrap();
+    writeln!(output, "    endfunction").unwrap();
+    writeln!(output).unwrap();
+    writeln!(output, "    virtual function void build();").unwrap();
+    for f in &fields {
+        let name = field_name(f);
+        let is_rand = if r.access == "RO" { 0 } else { 1 };
+        writeln!(output, "      {} = uvm_reg_field::type_id::create(\"{}\");", name, name).unwrap();
+        writeln!(output, "      {}.configure(this, {}, {}, \"{}\", 0, 0, 0, {}, 0);", name, f.range.bit_count(), f.range.lsb, r.access, is_rand).unwrap();
+    }
+    writeln!(output, "    endfunction").unwrap();
+    writeln!(output, "  endclass").unwrap();
+}
+
+/// Register block with a map for every register location type.
+/// Relative addresses are offsets from the base address of `rel_map`.
+fn uvm_block_class(output: &mut String, block_name: &str, registers: &[UvmRegister]) {
+    let map_name = |location: RegisterLocation| {
+        match location {
+            RegisterLocation::Index(_) => "index_map",
+            RegisterLocation::Absolute(_) => "abs_map",
+            RegisterLocation::Relative(_) => "rel_map",
+        }
+    };
+
+    let mut maps: Vec<&str> = vec![];
+    for name in ["rel_map", "abs_map", "index_map"] {
+        if registers.iter().any(|r| map_name(r.location) == name) {
+            maps.push(name);
+        }
+    }
+
+    let n_bytes = registers.iter()
+        .map(|r| r.register.size_in_bits as usize / 8)
+        .max()
+        .unwrap_or(RegisterSize::Size8 as usize / 8);
+
+    writeln!(output).unwrap();
+    writeln!(output, "  class {} extends uvm_reg_block;", block_name).unwrap();
+    writeln!(output, "    `uvm_object_utils({})", block_name).unwrap();
+    writeln!(output).unwrap();
+    for r in registers {
+        writeln!(output, "    rand {} {};", r.class_name, r.name).unwrap();
+    }
+    writeln!(output).unwrap();
+    for map in &maps {
+        writeln!(output, "    uvm_reg_map {};", map).unwrap();
+    }
+    writeln!(output).unwrap();
+    writeln!(output, "    function new(string name = \"{}\");", block_name).unwrap();
+    writeln!(output, "      super.new(name, UVM_NO_COVERAGE);").unwrap();
+    writeln!(output, "    endfunction").unwrap();
+    writeln!(output).unwrap();
+    writeln!(output, "    virtual function void build();").unwrap();
+    for map in &maps {
+        writeln!(output, "      {} = create_map(\"{}\", 0, {}, UVM_LITTLE_ENDIAN);", map, map, n_bytes).unwrap();
+    }
+    if let Some(map) = maps.first() {
+        writeln!(output, "      default_map = {};", map).unwrap();
+    }
+    for r in registers {
+        let location = match r.location {
+            RegisterLocation::Index(value) |
+            RegisterLocation::Absolute(value) |
+            RegisterLocation::Relative(value) => value,
+        };
+
+        writeln!(output).unwrap();
+        writeln!(output, "      {} = {}::type_id::create(\"{}\");", r.name, r.class_name, r.name).unwrap();
+        writeln!(output, "      {}.configure(this);", r.name).unwrap();
+        writeln!(output, "      {}.build();", r.name).unwrap();
+        writeln!(output, "      {}.add_reg({}, 'h{:x}, \"{}\");", map_name(r.location), r.name, location, r.access).unwrap();
+    }
+    writeln!(output).unwrap();
+    writeln!(output, "      lock_model();").unwrap();
+    writeln!(output, "    endfunction").unwrap();
+    writeln!(output, "  endclass").unwrap();
+}
+
+fn enum_type_name(register_prefix: &str, f: &RegisterFunction) -> String {
+    format!("{}_{}_e", register_prefix.to_lowercase(), f.name().unwrap().to_snake_case())
+}
+
+/// Reserved fields are named by their bit range.
+fn struct_member_name(f: &RegisterFunction) -> String {
+    match f.name() {
+        Some(_) => field_name(f),
+        None if f.range.msb == f.range.lsb => format!("reserved_{}", f.range.lsb),
+        None => format!("reserved_{}_{}", f.range.msb, f.range.lsb),
+    }
+}
+
+fn field_name(f: &RegisterFunction) -> String {
+    sv_name(f.name().unwrap())
+}
+
+/// Snake case name. Reserved names have postfix `_`.
+fn sv_name(name: &str) -> String {
+    let name = name.to_snake_case();
+
+    if RESERVED_NAMES.contains(&name.as_str()) {
+        format!("{}_", name)
+    } else {
+        name
+    }
+}
+
+fn logic_type(f: &RegisterFunction) -> String {
+    match f.range.bit_count().get() {
+        1 => "logic".to_string(),
+        bit_count => format!("logic [{}:0]", bit_count - 1),
+    }
+}
+
+fn register_comment(r: &Register) -> String {
+    match &r.description {
+        Some(description) => format!("{} ({}) - {}", comment(r.name.as_str()), r.access_mode, comment(description)),
+        None => format!("{} ({})", comment(r.name.as_str()), r.access_mode),
+    }
+}
+
+fn comment(text: &str) -> String {
+    text.replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::test_data;
+
+    #[test]
+    fn package() {
+        let package = parsed_file_to_sv_package(&test_data::parsed_file(test_data::DEVICE));
+
+        assert!(package.starts_with("// Generated from register description `Device`\n"));
+        assert!(package.contains("  localparam logic [31:0] TIMER_CONTROL_ABS_ADDRESS = 32'h40000000;\n"));
+        assert!(package.contains("    TIMER_CONTROL_MODE_ONE_SHOT = 4'd0, // Stop after one period\n"));
+        assert!(package.contains("    logic [19:0] reserved_31_12; // Bits 31:12\n"));
+        assert!(package.contains("    logic [5:0] byte_; // Bits 5:0\n"));
+        assert!(package.ends_with("endpackage\n"));
+    }
+
+    #[test]
+    fn uvm_package() {
+        let package = parsed_file_to_uvm_package(&test_data::parsed_file(test_data::DEVICE));
+
+        assert!(package.contains("  class timer_control_reg extends uvm_reg;\n"));
+        assert!(package.contains("      mode.configure(this, 4, 4, \"RW\", 0, 0, 0, 1, 0);\n"));
+        assert!(package.contains("      value.configure(this, 16, 0, \"RO\", 0, 0, 0, 0, 0);\n"));
+        assert!(package.contains("  class uart_block extends uvm_reg_block;\n"));
+    }
+}
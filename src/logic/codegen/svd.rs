@@ -0,0 +1,309 @@
+//! CMSIS-SVD device code generation.
+//!
+//! Register groups are peripherals. Base address of a peripheral with
+//! absolute register locations is the smallest absolute address of the
+//! group. Peripherals with relative register locations have base address
+//! zero, so the real base address must be set when the SVD file is used.
+//!
+//! SVD can't express index based register locations, so those are
+//! reported as errors.
+
+use std::{
+    fmt::Write as FmtWrite,
+    io::Write,
+    fs,
+};
+
+use inflections::Inflect;
+
+use crate::logic::validation::{
+    ParsedFile,
+    register::{
+        Register,
+        RegisterFunction,
+        RegisterEnum,
+        RegisterLocation,
+        AccessMode,
+    },
+    register_description::AddressSize,
+};
+
+pub fn parsed_file_to_svd(parsed_file: &ParsedFile, output: &str) {
+    let device = match parsed_file_to_svd_device(parsed_file) {
+        Ok(device) => device,
+        Err(errors) => {
+            for e in errors {
+                println!("error: {}", e);
+            }
+            println!("error: CMSIS-SVD generation failed");
+            std::process::exit(-1)
+        }
+    };
+
+    let mut f = fs::File::create(output).unwrap();
+    f.write_all(device.as_bytes()).unwrap();
+}
+
+/// SVD register. Registers which have different read and write
+/// locations are split to read-only and write-only SVD registers.
+struct SvdRegister<'a> {
+    register: &'a Register,
+    name: String,
+    access: AccessMode,
+    address: u64,
+}
+
+fn parsed_file_to_svd_device(parsed_file: &ParsedFile) -> Result<String, Vec<String>> {
+    let rd = &parsed_file.description;
+    let mut errors = vec![];
+    let mut peripherals = String::new();
+
+    for (group, registers) in parsed_file.register_groups() {
+        let peripheral_name = group.unwrap_or_else(|| rd.name.as_str());
+        match group_to_svd_peripheral(&mut peripherals, peripheral_name, registers) {
+            Ok(()) => (),
+            Err(group_errors) => errors.extend(group_errors),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let width = match rd.address_size {
+        AddressSize::Pointer => 32,
+        AddressSize::RegisterSize(size) => size as usize,
+    };
+    let description = rd.description.as_deref().unwrap_or_else(|| rd.name.as_str());
+
+    let mut output = String::new();
+    writeln!(output, "<?xml version=\"1.0\" encoding=\"utf-8\"?>").unwrap();
+    writeln!(output, "<!-- Generated from register description `{}` -->", xml_comment(rd.name.as_str())).unwrap();
+    writeln!(output, "<device schemaVersion=\"1.3\" xmlns:xs=\"http://www.w3.org/2001/XMLSchema-instance\" xs:noNamespaceSchemaLocation=\"CMSIS-SVD.xsd\">").unwrap();
+    writeln!(output, "  <name>{}</name>", svd_name(rd.name.as_str())).unwrap();
+    writeln!(output, "  <version>1.0</version>").unwrap();
+    writeln!(output, "  <description>{}</description>", xml_escape(description)).unwrap();
+    writeln!(output, "  <addressUnitBits>8</addressUnitBits>").unwrap();
+    writeln!(output, "  <width>{}</width>", width).unwrap();
+    writeln!(output, "  <peripherals>").unwrap();
+    output.push_str(&peripherals);
+    writeln!(output, "  </peripherals>").unwrap();
+    writeln!(output, "</device>").unwrap();
+
+    Ok(output)
+}
+
+fn group_to_svd_peripheral(output: &mut String, peripheral_name: &str, registers: &[Register]) -> Result<(), Vec<String>> {
+    let mut errors = vec![];
+    let mut svd_registers = vec![];
+    let mut absolute = false;
+    let mut relative = false;
+
+    for r in registers {
+        let locations = match r.access_mode {
+            AccessMode::ReadWrite if r.read_location != r.write_location => vec![
+                (format!("{}_R", svd_name(r.name.as_str())), AccessMode::Read, r.read_location),
+                (format!("{}_W", svd_name(r.name.as_str())), AccessMode::Write, r.write_location),
+            ],
+            AccessMode::Read | AccessMode::ReadWrite => vec![(svd_name(r.name.as_str()), r.access_mode, r.read_location)],
+            AccessMode::Write => vec![(svd_name(r.name.as_str()), r.access_mode, r.write_location)],
+        };
+
+        for (name, access, location) in locations {
+            let address = match location {
+                RegisterLocation::Absolute(address) => {
+                    absolute = true;
+                    address
+                }
+                RegisterLocation::Relative(address) => {
+                    relative = true;
+                    address
+                }
+                RegisterLocation::Index(_) => {
+                    errors.push(format!(
+                        "register '{}' of register group '{}' has index based location which CMSIS-SVD can't express",
+                        r.name.as_str(),
+                        peripheral_name,
+                    ));
+                    continue;
+                }
+            };
+
+            svd_registers.push(SvdRegister { register: r, name, access, address });
+        }
+    }
+
+    if absolute && relative {
+        errors.push(format!(
+            "register group '{}' has both absolute and relative register locations, CMSIS-SVD peripheral can only have one base address",
+            peripheral_name,
+        ));
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let base_address = svd_registers.iter().map(|r| r.address).min().filter(|_| absolute).unwrap_or(0);
+    let block_size = svd_registers.iter()
+        .map(|r| r.address - base_address + r.register.size_in_bits as u64 / 8)
+        .max()
+        .unwrap_or(0);
+
+    writeln!(output, "    <peripheral>").unwrap();
+    writeln!(output, "      <name>{}</name>", svd_name(peripheral_name)).unwrap();
+    if relative {
+        writeln!(output, "      <description>{} (base address is set at runtime)</description>", xml_escape(&peripheral_name.to_title_case())).unwrap();
+    } else {
+        writeln!(output, "      <description>{}</description>", xml_escape(&peripheral_name.to_title_case())).unwrap();
+    }
+    writeln!(output, "      <baseAddress>{:#x}</baseAddress>", base_address).unwrap();
+    writeln!(output, "      <addressBlock>").unwrap();
+    writeln!(output, "        <offset>0x0</offset>").unwrap();
+    writeln!(output, "        <size>{:#x}</size>", block_size).unwrap();
+    writeln!(output, "        <usage>registers</usage>").unwrap();
+    writeln!(output, "      </addressBlock>").unwrap();
+    writeln!(output, "      <registers>").unwrap();
+
+    for (i, r) in svd_registers.iter().enumerate() {
+        // Registers which share an address must be marked as alternates.
+        let alternate = svd_registers[..i].iter().find(|other| other.address == r.address);
+        register_to_svd(output, r, alternate, base_address);
+    }
+
+    writeln!(output, "      </registers>").unwrap();
+    writeln!(output, "    </peripheral>").unwrap();
+
+    Ok(())
+}
+
+fn register_to_svd(output: &mut String, r: &SvdRegister, alternate: Option<&SvdRegister>, base_address: u64) {
+    let description = r.register.description.as_deref().unwrap_or_else(|| r.register.name.as_str());
+
+    writeln!(output, "        <register>").unwrap();
+    writeln!(output, "          <name>{}</name>", r.name).unwrap();
+    writeln!(output, "          <description>{}</description>", xml_escape(description)).unwrap();
+    if let Some(alternate) = alternate {
+        writeln!(output, "          <alternateRegister>{}</alternateRegister>", alternate.name).unwrap();
+    }
+    writeln!(output, "          <addressOffset>{:#x}</addressOffset>", r.address - base_address).unwrap();
+    writeln!(output, "          <size>{}</size>", r.register.size_in_bits).unwrap();
+    writeln!(output, "          <access>{}</access>", svd_access(r.access)).unwrap();
+
+    let fields: Vec<&RegisterFunction> = r.register.functions.iter().filter(|f| f.status.is_normal()).collect();
+    if !fields.is_empty() {
+        writeln!(output, "          <fields>").unwrap();
+        for f in fields {
+            field_to_svd(output, r.register, f);
+        }
+        writeln!(output, "          </fields>").unwrap();
+    }
+
+    writeln!(output, "        </register>").unwrap();
+}
+
+fn field_to_svd(output: &mut String, r: &Register, f: &RegisterFunction) {
+    let name = f.name().unwrap();
+
+    writeln!(output, "            <field>").unwrap();
+    writeln!(output, "              <name>{}</name>", svd_name(name)).unwrap();
+    writeln!(output, "              <description>{}</description>", xml_escape(f.description().unwrap_or(name))).unwrap();
+    writeln!(output, "              <bitOffset>{}</bitOffset>", f.range.lsb).unwrap();
+    writeln!(output, "              <bitWidth>{}</bitWidth>", f.range.bit_count()).unwrap();
+
+    if let Some(e) = r.enums.iter().find(|e| e.range == f.range) {
+        enum_to_svd(output, e);
+    }
+
+    writeln!(output, "            </field>").unwrap();
+}
+
+fn enum_to_svd(output: &mut String, e: &RegisterEnum) {
+    writeln!(output, "              <enumeratedValues>").unwrap();
+    writeln!(output, "                <name>{}</name>", svd_name(e.name.as_str())).unwrap();
+    for v in &e.values {
+        writeln!(output, "                <enumeratedValue>").unwrap();
+        writeln!(output, "                  <name>{}</name>", svd_name(v.name.as_str())).unwrap();
+        if let Some(description) = &v.description {
+            writeln!(output, "                  <description>{}</description>", xml_escape(description)).unwrap();
+        }
+        writeln!(output, "                  <value>{}</value>", v.value).unwrap();
+        writeln!(output, "                </enumeratedValue>").unwrap();
+    }
+    writeln!(output, "              </enumeratedValues>").unwrap();
+}
+
+fn svd_access(access_mode: AccessMode) -> &'static str {
+    match access_mode {
+        AccessMode::Read => "read-only",
+        AccessMode::Write => "write-only",
+        AccessMode::ReadWrite => "read-write",
+    }
+}
+
+/// SVD names are C identifiers.
+fn svd_name(name: &str) -> String {
+    name.to_constant_case()
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn xml_comment(text: &str) -> String {
+    text.replace("--", "- -")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use crate::logic::{
+        import::{self, svd::svd_to_imported_file},
+        test_data,
+    };
+
+    /// SVD names are constant case and the importer uses the name as
+    /// description if the description is missing.
+    fn normalize(value: &mut Value) {
+        match value {
+            Value::Object(object) => {
+                object.remove("description");
+                if let Some(Value::String(name)) = object.get_mut("name") {
+                    *name = name.to_constant_case();
+                }
+                object.values_mut().for_each(normalize);
+            }
+            Value::Array(array) => array.iter_mut().for_each(normalize),
+            _ => (),
+        }
+    }
+
+    #[test]
+    fn import_of_export_is_equal() {
+        let parsed_file = test_data::parsed_file(test_data::DEVICE);
+
+        let text = parsed_file_to_svd_device(&parsed_file).unwrap();
+        let imported_file = svd_to_imported_file(&text).unwrap();
+        let toml = import::imported_file_to_toml(&imported_file, "test");
+        let imported = test_data::parsed_file(&toml);
+
+        let mut expected = test_data::ir_groups(&parsed_file);
+        let mut imported_groups = test_data::ir_groups(&imported);
+        assert_eq!(imported_groups[0]["registers"][0]["description"], "Timer control");
+        normalize(&mut expected);
+        normalize(&mut imported_groups);
+        assert_eq!(imported_groups, expected);
+    }
+
+    #[test]
+    fn different_write_location_is_split() {
+        let text = test_data::DEVICE.replace("absolute_address = 0x40001004", "absolute_address = 0x40001004\nabsolute_address_w = 0x40001008");
+        let parsed_file = test_data::parsed_file(&text);
+
+        let text = parsed_file_to_svd_device(&parsed_file).unwrap();
+        let imported_file = svd_to_imported_file(&text).unwrap();
+        let names: Vec<&str> = imported_file.groups[1].registers.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["DATA", "CONFIG_R", "CONFIG_W"]);
+    }
+}
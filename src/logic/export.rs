@@ -0,0 +1,50 @@
+//! Export of the validated register description.
+//!
+//! JSON and YAML exports use the intermediate representation
+//! from module `logic::ir`. Diagram exports are in module `diagram`
+//! and IP-XACT export is in module `ipxact`.
+
+pub mod diagram;
+pub mod ipxact;
+
+use std::{
+    fs,
+    io::{self, Write},
+};
+
+use crate::{
+    config::ExportFormat,
+    logic::{
+        ir::IrFile,
+        validation::ParsedFile,
+    },
+};
+
+pub fn parsed_file_to_export(parsed_file: &ParsedFile, output: Option<&str>, format: ExportFormat) {
+    let mut text = match format {
+        ExportFormat::Json => serde_json::to_string_pretty(&IrFile::new(parsed_file)).unwrap(),
+        ExportFormat::Yaml => serde_yaml::to_string(&IrFile::new(parsed_file)).unwrap(),
+        ExportFormat::Wavedrom => diagram::wavedrom(parsed_file),
+        ExportFormat::Svg => diagram::svg(parsed_file),
+        ExportFormat::Ascii => diagram::ascii(parsed_file),
+        ExportFormat::Ipxact => match ipxact::ipxact(parsed_file) {
+            Ok(text) => text,
+            Err(errors) => {
+                for e in errors {
+                    println!("error: {}", e);
+                }
+                println!("error: IP-XACT export failed");
+                std::process::exit(-1)
+            }
+        },
+    };
+
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+
+    match output {
+        Some(output) => fs::write(output, text.as_bytes()).unwrap(),
+        None => io::stdout().write_all(text.as_bytes()).unwrap(),
+    }
+}
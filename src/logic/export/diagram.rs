@@ -0,0 +1,214 @@
+//! Bit field layout diagrams of registers.
+//!
+//! Fields are drawn from the most significant bit to the least significant
+//! bit. Reserved fields are shaded and multi-bit fields are labeled with
+//! their bit range.
+
+use std::{
+    cmp::Reverse,
+    fmt::Write,
+};
+
+use inflections::Inflect;
+use serde_json::json;
+
+use crate::logic::validation::{
+    ParsedFile,
+    register::{
+        Register,
+        RegisterFunction,
+    },
+};
+
+/// Bits in one row of an SVG diagram.
+const SVG_LANE_BITS: usize = 16;
+const SVG_BIT_WIDTH: usize = 40;
+const SVG_BOX_HEIGHT: usize = 40;
+const SVG_MARGIN: usize = 10;
+const SVG_TITLE_HEIGHT: usize = 30;
+const SVG_BIT_NUMBER_HEIGHT: usize = 16;
+
+/// JSON array with a WaveDrom `reg` diagram for every register.
+pub fn wavedrom(parsed_file: &ParsedFile) -> String {
+    let mut diagrams = vec![];
+
+    for (group, registers) in parsed_file.register_groups() {
+        for r in registers {
+            // WaveDrom lists fields starting from the least significant bit.
+            // Fields without a name are drawn shaded.
+            let mut functions: Vec<&RegisterFunction> = r.functions.iter().collect();
+            functions.sort_by_key(|f| f.range.lsb);
+
+            let fields: Vec<serde_json::Value> = functions.iter().map(|f| {
+                let bits = f.range.bit_count().get();
+                match f.name() {
+                    None => json!({ "bits": bits }),
+                    Some(name) if bits == 1 => json!({ "bits": bits, "name": name }),
+                    Some(name) => json!({ "bits": bits, "name": name, "attr": f.range.to_string() }),
+                }
+            }).collect();
+
+            let size = r.size_in_bits as usize;
+
+            diagrams.push(json!({
+                "group": group,
+                "register": r.name.as_str(),
+                "diagram": {
+                    "reg": fields,
+                    "config": { "bits": size, "lanes": (size / SVG_LANE_BITS).max(1) },
+                },
+            }));
+        }
+    }
+
+    serde_json::to_string_pretty(&diagrams).unwrap()
+}
+
+/// Self-contained SVG image with diagrams of all registers.
+/// Registers larger than 16 bits are split to multiple rows.
+pub fn svg(parsed_file: &ParsedFile) -> String {
+    let mut body = String::new();
+    let mut y = SVG_MARGIN;
+
+    for (group, registers) in parsed_file.register_groups() {
+        for r in registers {
+            y = svg_register(&mut body, &title(group, r), r, y);
+        }
+    }
+
+    let width = SVG_LANE_BITS * SVG_BIT_WIDTH + 2 * SVG_MARGIN;
+    let height = y + SVG_MARGIN;
+
+    let mut output = String::new();
+    writeln!(output, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\" font-size=\"11\">", w = width, h = height).unwrap();
+    writeln!(output, "<rect width=\"{}\" height=\"{}\" fill=\"white\"/>", width, height).unwrap();
+    output.push_str(&body);
+    writeln!(output, "</svg>").unwrap();
+    output
+}
+
+/// Returns y coordinate after the diagram.
+fn svg_register(output: &mut String, title: &str, r: &Register, mut y: usize) -> usize {
+    let size = r.size_in_bits as usize;
+    let lanes = (size / SVG_LANE_BITS).max(1);
+    let lane_bits = size.min(SVG_LANE_BITS);
+
+    y += SVG_TITLE_HEIGHT;
+    writeln!(output, "<text x=\"{}\" y=\"{}\" font-size=\"14\" font-weight=\"bold\">{}</text>", SVG_MARGIN, y - 10, xml_escape(title)).unwrap();
+
+    for lane in (0..lanes).rev() {
+        let lane_lsb = lane * lane_bits;
+        let lane_msb = lane_lsb + lane_bits - 1;
+        let x_of_bit = |bit: usize| SVG_MARGIN + (lane_msb - bit) * SVG_BIT_WIDTH;
+
+        y += SVG_BIT_NUMBER_HEIGHT;
+        for bit in lane_lsb..=lane_msb {
+            writeln!(output, "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" fill=\"#666\">{}</text>", x_of_bit(bit) + SVG_BIT_WIDTH / 2, y - 4, bit).unwrap();
+        }
+
+        for f in fields_msb_first(r) {
+            let msb = (f.range.msb as usize).min(lane_msb);
+            let lsb = (f.range.lsb as usize).max(lane_lsb);
+            if msb < lsb {
+                continue;
+            }
+
+            let x = x_of_bit(msb);
+            let width = (msb - lsb + 1) * SVG_BIT_WIDTH;
+            let center = x + width / 2;
+            let fill = if f.status.is_reserved() { "#d0d0d0" } else { "white" };
+
+            writeln!(output, "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"black\"/>", x, y, width, SVG_BOX_HEIGHT, fill).unwrap();
+
+            match f.name() {
+                Some(name) if f.range.bit_count().get() == 1 => {
+                    writeln!(output, "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>", center, y + SVG_BOX_HEIGHT / 2 + 4, xml_escape(name)).unwrap();
+                }
+                Some(name) => {
+                    writeln!(output, "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>", center, y + 16, xml_escape(name)).unwrap();
+                    writeln!(output, "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\" fill=\"#666\">{}</text>", center, y + 32, f.range).unwrap();
+                }
+                None => (),
+            }
+        }
+
+        y += SVG_BOX_HEIGHT;
+    }
+
+    y
+}
+
+/// Box diagram where every field is wide enough for its label.
+pub fn ascii(parsed_file: &ParsedFile) -> String {
+    let mut output = String::new();
+
+    for (group, registers) in parsed_file.register_groups() {
+        for r in registers {
+            if !output.is_empty() {
+                writeln!(output).unwrap();
+            }
+            writeln!(output, "{} ({} bits)", title(group, r), r.size_in_bits).unwrap();
+            ascii_register(&mut output, r);
+        }
+    }
+
+    output
+}
+
+fn ascii_register(output: &mut String, r: &Register) {
+    let mut border = String::from("+");
+    let mut names = String::from("|");
+    let mut ranges = String::from("|");
+
+    for f in fields_msb_first(r) {
+        let range = f.range.to_string();
+        let (name, fill) = match f.name() {
+            Some(name) => (name.to_string(), ' '),
+            None => (String::new(), '/'),
+        };
+        let width = name.chars().count().max(range.len()) + 2;
+
+        border.push_str(&"-".repeat(width));
+        border.push('+');
+        names.push_str(&center(&name, width, fill));
+        names.push('|');
+        ranges.push_str(&center(&range, width, fill));
+        ranges.push('|');
+    }
+
+    writeln!(output, "{}", border).unwrap();
+    writeln!(output, "{}", names).unwrap();
+    writeln!(output, "{}", ranges).unwrap();
+    writeln!(output, "{}", border).unwrap();
+}
+
+/// Centers `text` to `width` characters. Padding is done with `fill`
+/// and text which is not empty is surrounded with spaces.
+fn center(text: &str, width: usize, fill: char) -> String {
+    let text = if text.is_empty() || fill == ' ' {
+        text.to_string()
+    } else {
+        format!(" {} ", text)
+    };
+    let padding = width.saturating_sub(text.chars().count());
+    let left = padding / 2;
+
+    format!("{}{}{}", fill.to_string().repeat(left), text, fill.to_string().repeat(padding - left))
+}
+
+fn fields_msb_first(r: &Register) -> Vec<&RegisterFunction> {
+    let mut functions: Vec<&RegisterFunction> = r.functions.iter().collect();
+    functions.sort_by_key(|f| Reverse(f.range.msb));
+    functions
+}
+
+fn title(group: Option<&str>, r: &Register) -> String {
+    match group {
+        Some(group) => format!("{} / {}", group.to_title_case(), r.name.as_str()),
+        None => r.name.as_str().to_string(),
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
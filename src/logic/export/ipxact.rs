@@ -0,0 +1,278 @@
+//! IP-XACT (IEEE 1685-2014) component export.
+//!
+//! Register groups are address blocks of one memory map. Base address of
+//! an address block is the smallest register address of the group.
+//! Names are converted to IP-XACT names by replacing spaces with
+//! underscores, so the original names are restored by the import.
+//!
+//! Reserved fields are exported as fields with element `reserved`.
+//! IP-XACT `enumeratedValues` elements don't have a name or description,
+//! so those are stored to vendor extensions of the field. Vendor
+//! extensions of address blocks store if the register locations are
+//! absolute or relative addresses. Registers which are split to
+//! read-only and write-only registers store the original register name
+//! to vendor extensions.
+//!
+//! Register description doesn't have reset values, so reset elements
+//! are not generated.
+
+use std::fmt::Write;
+
+use crate::logic::validation::{
+    ParsedFile,
+    register::{
+        Register,
+        RegisterFunction,
+        RegisterEnum,
+        RegisterLocation,
+        AccessMode,
+    },
+};
+
+pub const IPXACT_NAMESPACE: &str = "http://www.accellera.org/XMLSchema/IPXACT/1685-2014";
+pub const VENDOR_EXTENSION_NAMESPACE: &str = "urn:register-description-tools:ipxact";
+
+pub const LOCATION_KIND_ABSOLUTE: &str = "absolute";
+pub const LOCATION_KIND_RELATIVE: &str = "relative";
+
+/// IP-XACT register. Registers which have different read and write
+/// locations are split to read-only and write-only registers.
+struct IpxactRegister<'a> {
+    register: &'a Register,
+    name: String,
+    access: AccessMode,
+    address: u64,
+}
+
+pub fn ipxact(parsed_file: &ParsedFile) -> Result<String, Vec<String>> {
+    let rd = &parsed_file.description;
+    let mut errors = vec![];
+    let mut address_blocks = String::new();
+
+    for (group, registers) in parsed_file.register_groups() {
+        let block_name = group.unwrap_or_else(|| rd.name.as_str());
+        if let Err(group_errors) = group_to_address_block(&mut address_blocks, block_name, registers) {
+            errors.extend(group_errors);
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let mut output = String::new();
+    writeln!(output, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>").unwrap();
+    writeln!(output, "<!-- Generated from register description `{}` -->", xml_comment(rd.name.as_str())).unwrap();
+    writeln!(output, "<ipxact:component xmlns:ipxact=\"{ns}\" xmlns:rdt=\"{rdt}\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" xsi:schemaLocation=\"{ns} {ns}/index.xsd\">", ns = IPXACT_NAMESPACE, rdt = VENDOR_EXTENSION_NAMESPACE).unwrap();
+    writeln!(output, "  <ipxact:vendor>unknown</ipxact:vendor>").unwrap();
+    writeln!(output, "  <ipxact:library>registers</ipxact:library>").unwrap();
+    writeln!(output, "  <ipxact:name>{}</ipxact:name>", ipxact_name(rd.name.as_str())).unwrap();
+    writeln!(output, "  <ipxact:version>1.0</ipxact:version>").unwrap();
+    writeln!(output, "  <ipxact:memoryMaps>").unwrap();
+    writeln!(output, "    <ipxact:memoryMap>").unwrap();
+    writeln!(output, "      <ipxact:name>{}</ipxact:name>", ipxact_name(rd.name.as_str())).unwrap();
+    output.push_str(&address_blocks);
+    writeln!(output, "    </ipxact:memoryMap>").unwrap();
+    writeln!(output, "  </ipxact:memoryMaps>").unwrap();
+    if let Some(description) = &rd.description {
+        writeln!(output, "  <ipxact:description>{}</ipxact:description>", xml_escape(description)).unwrap();
+    }
+    writeln!(output, "</ipxact:component>").unwrap();
+
+    Ok(output)
+}
+
+fn group_to_address_block(output: &mut String, block_name: &str, registers: &[Register]) -> Result<(), Vec<String>> {
+    let mut errors = vec![];
+    let mut ipxact_registers = vec![];
+    let mut absolute = false;
+    let mut relative = false;
+
+    for r in registers {
+        let locations = match r.access_mode {
+            AccessMode::ReadWrite if r.read_location != r.write_location => vec![
+                (format!("{}_R", ipxact_name(r.name.as_str())), AccessMode::Read, r.read_location),
+                (format!("{}_W", ipxact_name(r.name.as_str())), AccessMode::Write, r.write_location),
+            ],
+            AccessMode::Read | AccessMode::ReadWrite => vec![(ipxact_name(r.name.as_str()), r.access_mode, r.read_location)],
+            AccessMode::Write => vec![(ipxact_name(r.name.as_str()), r.access_mode, r.write_location)],
+        };
+
+        for (name, access, location) in locations {
+            let address = match location {
+                RegisterLocation::Absolute(address) => {
+                    absolute = true;
+                    address
+                }
+                RegisterLocation::Relative(address) => {
+                    relative = true;
+                    address
+                }
+                RegisterLocation::Index(_) => {
+                    errors.push(format!(
+                        "register '{}' of register group '{}' has index based location which IP-XACT can't express",
+                        r.name.as_str(),
+                        block_name,
+                    ));
+                    continue;
+                }
+            };
+
+            ipxact_registers.push(IpxactRegister { register: r, name, access, address });
+        }
+    }
+
+    if absolute && relative {
+        errors.push(format!(
+            "register group '{}' has both absolute and relative register locations, IP-XACT address block can only have one base address",
+            block_name,
+        ));
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let base_address = ipxact_registers.iter().map(|r| r.address).min().unwrap_or(0);
+    let range = ipxact_registers.iter()
+        .map(|r| r.address - base_address + r.register.size_in_bits as u64 / 8)
+        .max()
+        .unwrap_or(1);
+    let width = registers.iter().map(|r| r.size_in_bits as usize).max().unwrap_or(8);
+    let location_kind = if absolute { LOCATION_KIND_ABSOLUTE } else { LOCATION_KIND_RELATIVE };
+
+    writeln!(output, "      <ipxact:addressBlock>").unwrap();
+    writeln!(output, "        <ipxact:name>{}</ipxact:name>", ipxact_name(block_name)).unwrap();
+    writeln!(output, "        <ipxact:baseAddress>'h{:x}</ipxact:baseAddress>", base_address).unwrap();
+    writeln!(output, "        <ipxact:range>'h{:x}</ipxact:range>", range).unwrap();
+    writeln!(output, "        <ipxact:width>{}</ipxact:width>", width).unwrap();
+    writeln!(output, "        <ipxact:usage>register</ipxact:usage>").unwrap();
+
+    for r in &ipxact_registers {
+        register_to_ipxact(output, r, base_address);
+    }
+
+    writeln!(output, "        <ipxact:vendorExtensions>").unwrap();
+    writeln!(output, "          <rdt:locationKind>{}</rdt:locationKind>", location_kind).unwrap();
+    writeln!(output, "        </ipxact:vendorExtensions>").unwrap();
+    writeln!(output, "      </ipxact:addressBlock>").unwrap();
+
+    Ok(())
+}
+
+fn register_to_ipxact(output: &mut String, r: &IpxactRegister, base_address: u64) {
+    writeln!(output, "        <ipxact:register>").unwrap();
+    writeln!(output, "          <ipxact:name>{}</ipxact:name>", r.name).unwrap();
+    if let Some(description) = &r.register.description {
+        writeln!(output, "          <ipxact:description>{}</ipxact:description>", xml_escape(description)).unwrap();
+    }
+    writeln!(output, "          <ipxact:addressOffset>'h{:x}</ipxact:addressOffset>", r.address - base_address).unwrap();
+    writeln!(output, "          <ipxact:size>{}</ipxact:size>", r.register.size_in_bits).unwrap();
+    writeln!(output, "          <ipxact:access>{}</ipxact:access>", ipxact_access(r.access)).unwrap();
+
+    for f in &r.register.functions {
+        field_to_ipxact(output, r, f);
+    }
+
+    if r.access != r.register.access_mode {
+        writeln!(output, "          <ipxact:vendorExtensions>").unwrap();
+        writeln!(output, "            <rdt:registerName>{}</rdt:registerName>", ipxact_name(r.register.name.as_str())).unwrap();
+        writeln!(output, "          </ipxact:vendorExtensions>").unwrap();
+    }
+
+    writeln!(output, "        </ipxact:register>").unwrap();
+}
+
+fn field_to_ipxact(output: &mut String, r: &IpxactRegister, f: &RegisterFunction) {
+    let name = match f.name() {
+        Some(name) => ipxact_name(name),
+        None => format!("reserved_{}", f.range.to_string().replace(':', "_")),
+    };
+
+    writeln!(output, "          <ipxact:field>").unwrap();
+    writeln!(output, "            <ipxact:name>{}</ipxact:name>", name).unwrap();
+    if let Some(description) = f.description() {
+        writeln!(output, "            <ipxact:description>{}</ipxact:description>", xml_escape(description)).unwrap();
+    }
+    writeln!(output, "            <ipxact:bitOffset>{}</ipxact:bitOffset>", f.range.lsb).unwrap();
+    writeln!(output, "            <ipxact:bitWidth>{}</ipxact:bitWidth>", f.range.bit_count()).unwrap();
+    writeln!(output, "            <ipxact:access>{}</ipxact:access>", ipxact_access(r.access)).unwrap();
+
+    if f.status.is_reserved() {
+        writeln!(output, "            <ipxact:reserved>true</ipxact:reserved>").unwrap();
+    } else if let Some(e) = r.register.enums.iter().find(|e| e.range == f.range) {
+        enum_to_ipxact(output, e);
+    }
+
+    writeln!(output, "          </ipxact:field>").unwrap();
+}
+
+fn enum_to_ipxact(output: &mut String, e: &RegisterEnum) {
+    writeln!(output, "            <ipxact:enumeratedValues>").unwrap();
+    for v in &e.values {
+        writeln!(output, "              <ipxact:enumeratedValue>").unwrap();
+        writeln!(output, "                <ipxact:name>{}</ipxact:name>", ipxact_name(v.name.as_str())).unwrap();
+        if let Some(description) = &v.description {
+            writeln!(output, "                <ipxact:description>{}</ipxact:description>", xml_escape(description)).unwrap();
+        }
+        writeln!(output, "                <ipxact:value>'h{:x}</ipxact:value>", v.value).unwrap();
+        writeln!(output, "              </ipxact:enumeratedValue>").unwrap();
+    }
+    writeln!(output, "            </ipxact:enumeratedValues>").unwrap();
+
+    writeln!(output, "            <ipxact:vendorExtensions>").unwrap();
+    writeln!(output, "              <rdt:enumName>{}</rdt:enumName>", ipxact_name(e.name.as_str())).unwrap();
+    if let Some(description) = &e.description {
+        writeln!(output, "              <rdt:enumDescription>{}</rdt:enumDescription>", xml_escape(description)).unwrap();
+    }
+    writeln!(output, "            </ipxact:vendorExtensions>").unwrap();
+}
+
+fn ipxact_access(access_mode: AccessMode) -> &'static str {
+    match access_mode {
+        AccessMode::Read => "read-only",
+        AccessMode::Write => "write-only",
+        AccessMode::ReadWrite => "read-write",
+    }
+}
+
+/// IP-XACT names can't contain spaces.
+fn ipxact_name(name: &str) -> String {
+    name.replace(' ', "_")
+}
+
+fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn xml_comment(text: &str) -> String {
+    text.replace("--", "- -")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logic::{
+        import::{self, ipxact::ipxact_to_imported_file},
+        test_data,
+    };
+
+    #[test]
+    fn import_of_export_is_equal() {
+        let parsed_file = test_data::parsed_file(test_data::DEVICE);
+
+        let text = ipxact(&parsed_file).unwrap();
+        let imported_file = ipxact_to_imported_file(&text).unwrap();
+        let toml = import::imported_file_to_toml(&imported_file, "test");
+        let imported = test_data::parsed_file(&toml);
+
+        assert_eq!(imported.description.name.as_str(), "Device");
+        assert_eq!(test_data::ir_groups(&imported), test_data::ir_groups(&parsed_file));
+    }
+
+    #[test]
+    fn index_location_is_error() {
+        let parsed_file = test_data::parsed_file(&test_data::DEVICE.replace("absolute_address = 0x40001004", "index = 4"));
+        assert_eq!(ipxact(&parsed_file).unwrap_err().len(), 1);
+    }
+}
@@ -0,0 +1,236 @@
+//! Import of register descriptions from other formats.
+//!
+//! Importers convert the input file to `ImportedFile` which is then
+//! written as register description TOML. Bit ranges which are not covered
+//! by any field are filled with reserved fields, so that the imported
+//! registers are completely defined.
+
+pub mod svd;
+pub mod ipxact;
+pub mod rdl;
+
+use std::fmt::Write;
+
+use inflections::Inflect;
+
+use crate::logic::validation::register::{
+    AccessMode,
+    BitRange,
+    RegisterLocation,
+    RegisterSize,
+};
+
+pub struct ImportedFile {
+    pub name: String,
+    pub description: Option<String>,
+    pub default_register_size: Option<RegisterSize>,
+    pub default_register_access: Option<AccessMode>,
+    pub groups: Vec<ImportedGroup>,
+}
+
+#[derive(Clone)]
+pub struct ImportedGroup {
+    pub name: String,
+    pub registers: Vec<ImportedRegister>,
+}
+
+#[derive(Clone)]
+pub struct ImportedRegister {
+    pub name: String,
+    pub description: Option<String>,
+    pub access: AccessMode,
+    pub size: RegisterSize,
+    pub location: RegisterLocation,
+    /// Write location if it is different from `location`.
+    pub write_location: Option<RegisterLocation>,
+    pub fields: Vec<ImportedField>,
+    pub enums: Vec<ImportedEnum>,
+}
+
+#[derive(Clone)]
+pub struct ImportedField {
+    pub range: BitRange,
+    /// Field is reserved if name is `None`.
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct ImportedEnum {
+    pub name: String,
+    pub description: Option<String>,
+    pub range: BitRange,
+    pub values: Vec<ImportedEnumValue>,
+}
+
+#[derive(Clone)]
+pub struct ImportedEnumValue {
+    pub value: u64,
+    pub name: String,
+    pub description: Option<String>,
+}
+
+/// Register description TOML. Comment `# Imported from <source>` is
+/// added to the beginning of the file.
+pub fn imported_file_to_toml(file: &ImportedFile, source: &str) -> String {
+    let mut output = String::new();
+
+    writeln!(output, "# Imported from {}", source.replace('\n', " ")).unwrap();
+    writeln!(output).unwrap();
+    writeln!(output, "[register_description]").unwrap();
+    writeln!(output, "name = {}", toml_string(&name(&file.name))).unwrap();
+    writeln!(output, "version = \"0.1\"").unwrap();
+    if let Some(description) = &file.description {
+        writeln!(output, "description = {}", toml_string(description)).unwrap();
+    }
+    if let Some(size) = file.default_register_size {
+        writeln!(output, "default_register_size = \"{}\"", size).unwrap();
+    }
+    if let Some(access) = file.default_register_access {
+        writeln!(output, "default_register_access = \"{}\"", access).unwrap();
+    }
+
+    for group in &file.groups {
+        let group_name = group_name(&group.name);
+        for r in &group.registers {
+            register_to_toml(&mut output, file, &group_name, r);
+        }
+    }
+
+    output
+}
+
+fn register_to_toml(output: &mut String, file: &ImportedFile, group: &str, r: &ImportedRegister) {
+    writeln!(output).unwrap();
+    writeln!(output, "[[register.{}]]", group).unwrap();
+    writeln!(output, "name = {}", toml_string(&name(&r.name))).unwrap();
+    if let Some(description) = &r.description {
+        writeln!(output, "description = {}", toml_string(description)).unwrap();
+    }
+
+    writeln!(output, "{}", location_key_value(r.location, "")).unwrap();
+    if let Some(write_location) = r.write_location {
+        writeln!(output, "{}", location_key_value(write_location, "_w")).unwrap();
+    }
+
+    if file.default_register_access != Some(r.access) {
+        writeln!(output, "access = \"{}\"", r.access).unwrap();
+    }
+    if file.default_register_size != Some(r.size) {
+        writeln!(output, "size = \"{}\"", r.size).unwrap();
+    }
+
+    writeln!(output, "bit_fields = [").unwrap();
+    for f in fill_empty_fields_as_reserved(&r.fields, r.size) {
+        write!(output, "    {{ bit = \"{}\"", f.range).unwrap();
+        match &f.name {
+            Some(field_name) => write!(output, ", name = {}", toml_string(&name(field_name))).unwrap(),
+            None => write!(output, ", reserved = true").unwrap(),
+        }
+        if let (Some(_), Some(description)) = (&f.name, &f.description) {
+            write!(output, ", description = {}", toml_string(description)).unwrap();
+        }
+        writeln!(output, " }},").unwrap();
+    }
+    writeln!(output, "]").unwrap();
+
+    for e in &r.enums {
+        writeln!(output).unwrap();
+        writeln!(output, "[[register.{}.enum]]", group).unwrap();
+        writeln!(output, "name = {}", toml_string(&name(&e.name))).unwrap();
+        if let Some(description) = &e.description {
+            writeln!(output, "description = {}", toml_string(description)).unwrap();
+        }
+        writeln!(output, "bit = \"{}\"", e.range).unwrap();
+        writeln!(output, "values = [").unwrap();
+        for v in &e.values {
+            write!(output, "    {{ value = {:#x}, name = {}", v.value, toml_string(&name(&v.name))).unwrap();
+            if let Some(description) = &v.description {
+                write!(output, ", description = {}", toml_string(description)).unwrap();
+            }
+            writeln!(output, " }},").unwrap();
+        }
+        writeln!(output, "]").unwrap();
+    }
+}
+
+fn location_key_value(location: RegisterLocation, key_postfix: &str) -> String {
+    match location {
+        RegisterLocation::Index(value) => format!("index{} = {:#x}", key_postfix, value),
+        RegisterLocation::Relative(value) => format!("relative_address{} = {:#x}", key_postfix, value),
+        RegisterLocation::Absolute(value) => format!("absolute_address{} = {:#x}", key_postfix, value),
+    }
+}
+
+/// Fields sorted from the most significant bit to the least significant
+/// bit. Bit ranges without a field are added as reserved fields.
+///
+/// Overlapping fields and fields outside the register are kept as is,
+/// so that validation of the imported file reports them.
+pub fn fill_empty_fields_as_reserved(fields: &[ImportedField], size: RegisterSize) -> Vec<ImportedField> {
+    let mut fields: Vec<&ImportedField> = fields.iter().collect();
+    fields.sort_by_key(|f| std::cmp::Reverse(f.range.msb));
+    let ranges: Vec<BitRange> = fields.iter().map(|f| f.range).collect();
+
+    BitRange::fill_gaps(&ranges, size).into_iter().map(|(range, i)| {
+        match i {
+            Some(i) => fields[i].clone(),
+            None => ImportedField { range, name: None, description: None },
+        }
+    }).collect()
+}
+
+/// Converts text to a register description name. Characters which
+/// are not allowed in names are converted to spaces. Names which don't
+/// start with a letter get prefix `n`.
+pub fn name(text: &str) -> String {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_string())
+        .collect();
+    let name = words.join(" ");
+
+    match name.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => name,
+        Some(_) => format!("n{}", name),
+        None => "unnamed".to_string(),
+    }
+}
+
+/// Register groups are TOML keys, so group names are converted
+/// to snake case.
+pub fn group_name(text: &str) -> String {
+    name(text).to_snake_case()
+}
+
+/// Whitespace of multi-line descriptions is collapsed to single spaces.
+/// Empty descriptions are `None`.
+pub fn description(text: &str) -> Option<String> {
+    let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+
+    if text.is_empty() {
+        None
+    } else {
+        Some(text)
+    }
+}
+
+fn toml_string(text: &str) -> String {
+    toml::Value::String(text.to_string()).to_string()
+}
+
+/// First child element with tag name `name`. Namespace of the tag is ignored.
+fn child<'a, 'input>(node: roxmltree::Node<'a, 'input>, name: &str) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children().find(|n| n.is_element() && n.tag_name().name() == name)
+}
+
+/// Child elements with tag name `name`. Namespace of the tag is ignored.
+fn children<'a, 'input: 'a>(node: roxmltree::Node<'a, 'input>, name: &'a str) -> impl Iterator<Item=roxmltree::Node<'a, 'input>> + 'a {
+    node.children().filter(move |n| n.is_element() && n.tag_name().name() == name)
+}
+
+/// Trimmed text of the first child element with tag name `name`.
+fn child_text<'a>(node: roxmltree::Node<'a, '_>, name: &str) -> Option<&'a str> {
+    child(node, name).and_then(|n| n.text()).map(|text| text.trim())
+}
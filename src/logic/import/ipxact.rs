@@ -0,0 +1,451 @@
+//! IP-XACT (IEEE 1685) component import.
+//!
+//! Address blocks of memory maps are register groups. Register locations
+//! are relative addresses unless vendor extensions written by the IP-XACT
+//! export mark the address block to have absolute addresses. Registers of
+//! register files are added to the address block of the register file with
+//! register file name as a name prefix.
+//!
+//! Read-only and write-only registers which the IP-XACT export split from
+//! one register are merged back together.
+//!
+//! Elements are matched without namespace, so IP-XACT 1685-2009
+//! (`spirit`) and 1685-2014 (`ipxact`) files are both supported.
+//!
+//! Register description doesn't have reset values, so reset values are
+//! not imported. Other constructs which the register description format
+//! can't express are reported as warnings.
+
+use std::convert::TryFrom;
+
+use roxmltree::{Document, Node};
+
+use crate::logic::{
+    export::ipxact::{
+        LOCATION_KIND_ABSOLUTE,
+        LOCATION_KIND_RELATIVE,
+    },
+    validation::register::{
+        AccessMode,
+        BitRange,
+        RegisterLocation,
+        RegisterSize,
+    },
+};
+
+use super::{
+    ImportedFile,
+    ImportedGroup,
+    ImportedRegister,
+    ImportedField,
+    ImportedEnum,
+    ImportedEnumValue,
+    child,
+    children,
+    child_text,
+    description,
+};
+
+struct IpxactImporter {
+    errors: Vec<String>,
+    /// Count of registers which have reset values.
+    registers_with_reset: usize,
+}
+
+pub fn ipxact_to_imported_file(text: &str) -> Result<ImportedFile, Vec<String>> {
+    let document = Document::parse(text).map_err(|e| vec![format!("XML parsing failed: {}", e)])?;
+    let component = document.root_element();
+
+    if component.tag_name().name() != "component" {
+        return Err(vec![format!("expected root element 'component', found '{}'", component.tag_name().name())]);
+    }
+
+    let mut importer = IpxactImporter {
+        errors: vec![],
+        registers_with_reset: 0,
+    };
+
+    let name = importer.required_text(component, "name", "component").unwrap_or("component").to_string();
+
+    let memory_maps: Vec<Node> = child(component, "memoryMaps")
+        .map(|maps| children(maps, "memoryMap").collect())
+        .unwrap_or_default();
+
+    let mut groups = vec![];
+    for memory_map in &memory_maps {
+        let map_name = importer.required_text(*memory_map, "name", "component").unwrap_or("");
+
+        for node in memory_map.children().filter(|n| n.is_element()) {
+            match node.tag_name().name() {
+                "addressBlock" => {
+                    // Group names must be unique, so address blocks of
+                    // different memory maps are prefixed with the memory map name.
+                    let name_prefix = if memory_maps.len() > 1 { format!("{} ", map_name) } else { String::new() };
+                    if let Some(group) = importer.address_block(node, &name_prefix) {
+                        groups.push(group);
+                    }
+                }
+                "bank" | "subspaceMap" => {
+                    println!("warning: element '{}' of memory map '{}' is not supported and it is not imported", node.tag_name().name(), map_name);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    if importer.registers_with_reset > 0 {
+        println!("warning: reset values of {} registers are not imported because register description doesn't have reset values", importer.registers_with_reset);
+    }
+
+    if !importer.errors.is_empty() {
+        return Err(importer.errors);
+    }
+
+    Ok(ImportedFile {
+        name,
+        description: child_text(component, "description").and_then(description),
+        default_register_size: None,
+        default_register_access: None,
+        groups,
+    })
+}
+
+impl IpxactImporter {
+    fn address_block(&mut self, block: Node, name_prefix: &str) -> Option<ImportedGroup> {
+        let name = format!("{}{}", name_prefix, self.required_text(block, "name", "memory map")?);
+        let context = format!("address block '{}'", name);
+
+        let base_address = match child_text(block, "baseAddress") {
+            Some(address) => self.integer(address, &context)?,
+            None => {
+                self.errors.push(format!("{} does not have element 'baseAddress'", context));
+                return None;
+            }
+        };
+
+        let location_kind = child(block, "vendorExtensions")
+            .and_then(|extensions| child_text(extensions, "locationKind"))
+            .unwrap_or(LOCATION_KIND_RELATIVE);
+        let absolute = match location_kind {
+            LOCATION_KIND_ABSOLUTE => true,
+            LOCATION_KIND_RELATIVE => false,
+            unknown => {
+                self.errors.push(format!("{} has unknown location kind '{}'", context, unknown));
+                return None;
+            }
+        };
+
+        let access = match child_text(block, "access") {
+            Some(access) => self.access(access, &context),
+            None => None,
+        };
+
+        let mut registers = vec![];
+        self.registers(block, "", base_address, absolute, access, &mut registers);
+
+        Some(ImportedGroup {
+            name,
+            registers,
+        })
+    }
+
+    /// Registers and register files of element `parent`.
+    fn registers(&mut self, parent: Node, name_prefix: &str, address: u64, absolute: bool, access: Option<AccessMode>, registers: &mut Vec<ImportedRegister>) {
+        for node in parent.children().filter(|n| n.is_element()) {
+            match node.tag_name().name() {
+                "register" => {
+                    if let Some(r) = self.register(node, name_prefix, address, absolute, access) {
+                        let split_from = child(node, "vendorExtensions")
+                            .and_then(|extensions| child_text(extensions, "registerName"))
+                            .map(|name| format!("{}{}", name_prefix, name));
+                        match split_from {
+                            Some(name) => merge_split_register(registers, r, name),
+                            None => registers.push(r),
+                        }
+                    }
+                }
+                "registerFile" => {
+                    let name = child_text(node, "name").unwrap_or("");
+                    let context = format!("register file '{}{}'", name_prefix, name);
+                    self.warn_about_array(node, &context);
+
+                    let offset = match child_text(node, "addressOffset") {
+                        Some(offset) => self.integer(offset, &context).unwrap_or(0),
+                        None => {
+                            self.errors.push(format!("{} does not have element 'addressOffset'", context));
+                            continue;
+                        }
+                    };
+
+                    let name_prefix = format!("{}{} ", name_prefix, name);
+                    self.registers(node, &name_prefix, address + offset, absolute, access, registers);
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn register(&mut self, register: Node, name_prefix: &str, address: u64, absolute: bool, block_access: Option<AccessMode>) -> Option<ImportedRegister> {
+        let name = format!("{}{}", name_prefix, self.required_text(register, "name", "register")?);
+        let context = format!("register '{}'", name);
+        self.warn_about_array(register, &context);
+
+        if child(register, "alternateRegisters").is_some() {
+            println!("warning: alternate registers of {} are not supported and they are not imported", context);
+        }
+
+        let offset = match child_text(register, "addressOffset") {
+            Some(offset) => self.integer(offset, &context)?,
+            None => {
+                self.errors.push(format!("{} does not have element 'addressOffset'", context));
+                return None;
+            }
+        };
+
+        let size = match child_text(register, "size") {
+            Some(size) => match self.integer(size, &context).map(|size| RegisterSize::try_from(size.to_string().as_str())) {
+                Some(Ok(size)) => size,
+                Some(Err(e)) => {
+                    self.errors.push(format!("{} has {}", context, e));
+                    return None;
+                }
+                None => return None,
+            },
+            None => {
+                self.errors.push(format!("{} does not have element 'size'", context));
+                return None;
+            }
+        };
+
+        let mut fields = vec![];
+        let mut enums = vec![];
+        let mut field_access = vec![];
+        let mut reset = child(register, "reset").is_some();
+
+        for field in children(register, "field") {
+            reset |= child(field, "resets").is_some() || child(field, "reset").is_some();
+
+            if let Some(access) = child_text(field, "access") {
+                field_access.extend(self.access(access, &context));
+            }
+
+            self.field(field, &context, &mut fields, &mut enums);
+        }
+
+        if reset {
+            self.registers_with_reset += 1;
+        }
+
+        // Register access is optional in IP-XACT, so it
+        // can also be defined in the address block or fields.
+        let access = match child_text(register, "access") {
+            Some(access) => self.access(access, &context),
+            None => None,
+        };
+        let access = access.or(block_access).unwrap_or_else(|| {
+            if !field_access.is_empty() && field_access.iter().all(|a| *a == AccessMode::Read) {
+                AccessMode::Read
+            } else if !field_access.is_empty() && field_access.iter().all(|a| *a == AccessMode::Write) {
+                AccessMode::Write
+            } else {
+                AccessMode::ReadWrite
+            }
+        });
+
+        let location = if absolute {
+            RegisterLocation::Absolute(address + offset)
+        } else {
+            RegisterLocation::Relative(address + offset)
+        };
+
+        Some(ImportedRegister {
+            name,
+            description: child_text(register, "description").and_then(description),
+            access,
+            size,
+            location,
+            write_location: None,
+            fields,
+            enums,
+        })
+    }
+
+    fn field(&mut self, field: Node, register_context: &str, fields: &mut Vec<ImportedField>, enums: &mut Vec<ImportedEnum>) {
+        let name = match self.required_text(field, "name", register_context) {
+            Some(name) => name.to_string(),
+            None => return,
+        };
+        let context = format!("field '{}' of {}", name, register_context);
+
+        let lsb = match child_text(field, "bitOffset") {
+            Some(offset) => self.integer(offset, &context),
+            None => {
+                self.errors.push(format!("{} does not have element 'bitOffset'", context));
+                return;
+            }
+        };
+        let width = match child_text(field, "bitWidth") {
+            Some(width) => self.integer(width, &context),
+            None => {
+                self.errors.push(format!("{} does not have element 'bitWidth'", context));
+                return;
+            }
+        };
+        let range = match (lsb, width) {
+            (Some(lsb), Some(width)) if width > 0 && lsb + width - 1 <= u64::from(u16::MAX) => {
+                BitRange::new((lsb + width - 1) as u16, lsb as u16)
+            }
+            (Some(lsb), Some(width)) => {
+                self.errors.push(format!("{} has invalid bit range, bit offset: {}, bit width: {}", context, lsb, width));
+                return;
+            }
+            _ => return,
+        };
+
+        let reserved = match child_text(field, "reserved") {
+            Some("true") | Some("1") => true,
+            Some("false") | Some("0") | None => false,
+            Some(expression) => {
+                println!("warning: {} has unsupported element 'reserved' value '{}', field is not reserved", context, expression);
+                false
+            }
+        };
+
+        if reserved {
+            fields.push(ImportedField {
+                range,
+                name: None,
+                description: None,
+            });
+            return;
+        }
+
+        if let Some(values) = child(field, "enumeratedValues") {
+            let extensions = child(field, "vendorExtensions");
+            let enum_name = extensions.and_then(|extensions| child_text(extensions, "enumName")).unwrap_or(&name);
+            let enum_description = extensions.and_then(|extensions| child_text(extensions, "enumDescription"));
+
+            let mut enum_values = vec![];
+            for value in children(values, "enumeratedValue") {
+                let value_name = match self.required_text(value, "name", &context) {
+                    Some(value_name) => value_name.to_string(),
+                    None => continue,
+                };
+                let number = match child_text(value, "value") {
+                    Some(number) => self.integer(number, &context),
+                    None => {
+                        self.errors.push(format!("enumerated value '{}' of {} does not have element 'value'", value_name, context));
+                        continue;
+                    }
+                };
+
+                if let Some(number) = number {
+                    enum_values.push(ImportedEnumValue {
+                        value: number,
+                        name: value_name,
+                        description: child_text(value, "description").and_then(description),
+                    });
+                }
+            }
+
+            if !enum_values.is_empty() {
+                enums.push(ImportedEnum {
+                    name: enum_name.to_string(),
+                    description: enum_description.and_then(description),
+                    range,
+                    values: enum_values,
+                });
+            }
+        }
+
+        fields.push(ImportedField {
+            range,
+            name: Some(name),
+            description: child_text(field, "description").and_then(description),
+        });
+    }
+
+    fn warn_about_array(&mut self, node: Node, context: &str) {
+        if child(node, "dim").is_some() {
+            println!("warning: {} is an array, only the first element is imported", context);
+        }
+    }
+
+    fn access(&mut self, access: &str, context: &str) -> Option<AccessMode> {
+        match access {
+            "read-only" => Some(AccessMode::Read),
+            "write-only" | "writeOnce" => Some(AccessMode::Write),
+            "read-write" | "read-writeOnce" => Some(AccessMode::ReadWrite),
+            unknown => {
+                self.errors.push(format!("{} has unknown access '{}'", context, unknown));
+                None
+            }
+        }
+    }
+
+    fn required_text<'a>(&mut self, node: Node<'a, '_>, name: &str, context: &str) -> Option<&'a str> {
+        let text = child_text(node, name);
+        if text.is_none() {
+            self.errors.push(format!("element '{}' is missing from element '{}' of {}", name, node.tag_name().name(), context));
+        }
+        text
+    }
+
+    /// Integer in SystemVerilog literal format like `'h1F` or `8'b1`,
+    /// with prefix `0x` or `#` (hexadecimal) or decimal number with
+    /// optional multiplier postfix `k`, `M`, `G` or `T`.
+    fn integer(&mut self, text: &str, context: &str) -> Option<u64> {
+        let text = text.trim().replace('_', "");
+
+        let value = if let Some(i) = text.find('\'') {
+            let literal = text[i + 1..].trim_start_matches(['s', 'S']);
+            let radix = match literal.chars().next() {
+                Some('h') | Some('H') => Some(16),
+                Some('d') | Some('D') => Some(10),
+                Some('o') | Some('O') => Some(8),
+                Some('b') | Some('B') => Some(2),
+                _ => None,
+            };
+            radix.and_then(|radix| u64::from_str_radix(&literal[1..], radix).ok())
+        } else if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")).or_else(|| text.strip_prefix('#')) {
+            u64::from_str_radix(hex, 16).ok()
+        } else {
+            let (number, multiplier) = match text.chars().last() {
+                Some('k') | Some('K') => (&text[..text.len() - 1], 1 << 10),
+                Some('m') | Some('M') => (&text[..text.len() - 1], 1 << 20),
+                Some('g') | Some('G') => (&text[..text.len() - 1], 1 << 30),
+                Some('t') | Some('T') => (&text[..text.len() - 1], 1 << 40),
+                _ => (text.as_str(), 1),
+            };
+            number.parse::<u64>().ok().and_then(|value| value.checked_mul(multiplier))
+        };
+
+        if value.is_none() {
+            self.errors.push(format!("{} has unsupported integer or expression '{}'", context, text));
+        }
+
+        value
+    }
+}
+
+/// Merges register which has different read and write locations.
+/// The first part is added as is and the second part sets the
+/// write location.
+fn merge_split_register(registers: &mut Vec<ImportedRegister>, mut part: ImportedRegister, name: String) {
+    match registers.iter_mut().find(|r| r.name == name) {
+        Some(r) => {
+            match part.access {
+                AccessMode::Write => r.write_location = Some(part.location),
+                _ => {
+                    r.write_location = Some(r.location);
+                    r.location = part.location;
+                }
+            }
+            r.access = AccessMode::ReadWrite;
+        }
+        None => {
+            part.name = name;
+            registers.push(part);
+        }
+    }
+}
@@ -0,0 +1,1179 @@
+//! SystemRDL import.
+//!
+//! Supported subset is `addrmap`, `regfile`, `reg` and `field` components,
+//! `enum` definitions and the properties `name`, `desc`, `sw`, `hw`,
+//! `regwidth`, `fieldwidth` and `encode`. Component definitions can be
+//! named or anonymous and properties can have default values. Default
+//! values and definitions are resolved from the scope where the component
+//! is defined.
+//!
+//! The root address map is the last address map definition which is not
+//! instantiated in other components. Registers of the root address map are
+//! in a register group with the name of the root address map and every
+//! `regfile` or `addrmap` instance of the root address map is a register
+//! group. Registers of deeper hierarchy levels have the instance names as
+//! a name prefix. Register locations are relative addresses from the root
+//! address map. Addresses which are not defined are allocated like
+//! SystemRDL `regalign` addressing mode does.
+//!
+//! Register access is combined from the `sw` property of fields. Fields
+//! with `sw = na` are reserved fields. Property `hw` is accepted but it
+//! doesn't affect the imported register description. Reset values are not
+//! imported because register description doesn't have reset values.
+//!
+//! Unsupported constructs are reported as warnings and left out from the
+//! imported file.
+
+use std::collections::HashMap;
+
+use crate::logic::validation::register::{
+    AccessMode,
+    BitRange,
+    RegisterLocation,
+    RegisterSize,
+};
+
+use super::{
+    ImportedFile,
+    ImportedGroup,
+    ImportedRegister,
+    ImportedField,
+    ImportedEnum,
+    ImportedEnumValue,
+    description,
+};
+
+const DEFAULT_REGWIDTH: u64 = 32;
+const DEFAULT_FIELDWIDTH: u64 = 1;
+
+/// Properties which are imported or which don't affect the import.
+const SUPPORTED_PROPERTIES: &[&str] = &[
+    "name", "desc", "sw", "hw", "regwidth", "fieldwidth", "encode", "reset",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(u64),
+    Str(String),
+    Punct(&'static str),
+}
+
+const PUNCTUATION: &[&str] = &[
+    "+=", "%=", "->", "::", "{", "}", ";", "=", "[", "]", ":", "@", ",", ".",
+    "(", ")", "#", "?", "!", "&", "|", "^", "~", "<", ">", "+", "-", "*", "/", "%",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ComponentKind {
+    Addrmap,
+    Regfile,
+    Reg,
+    Field,
+}
+
+impl ComponentKind {
+    fn from_keyword(keyword: &str) -> Option<Self> {
+        match keyword {
+            "addrmap" => Some(ComponentKind::Addrmap),
+            "regfile" => Some(ComponentKind::Regfile),
+            "reg" => Some(ComponentKind::Reg),
+            "field" => Some(ComponentKind::Field),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Component {
+    kind: ComponentKind,
+    /// Name of the component definition. Anonymous components don't have a name.
+    type_name: Option<String>,
+    items: Vec<Item>,
+}
+
+#[derive(Debug)]
+enum Item {
+    Property { name: String, value: Value, default: bool },
+    Definition(Component),
+    Instances { component: ComponentRef, instances: Vec<Instance>, line: usize },
+    Enum(EnumDefinition),
+}
+
+#[derive(Debug)]
+enum ComponentRef {
+    Anonymous(Component),
+    Type(String),
+}
+
+#[derive(Debug)]
+enum Value {
+    Number(u64),
+    Str(String),
+    Ident(String),
+}
+
+#[derive(Debug)]
+enum Brackets {
+    /// Array size or field width.
+    Count(u64),
+    Range { msb: u64, lsb: u64 },
+}
+
+#[derive(Debug)]
+struct Instance {
+    name: String,
+    brackets: Option<Brackets>,
+    address: Option<u64>,
+    stride: Option<u64>,
+    alignment: Option<u64>,
+}
+
+#[derive(Debug)]
+struct EnumDefinition {
+    name: String,
+    entries: Vec<EnumEntry>,
+}
+
+#[derive(Debug)]
+struct EnumEntry {
+    name: String,
+    value: u64,
+    description: Option<String>,
+}
+
+pub fn rdl_to_imported_file(text: &str) -> Result<ImportedFile, Vec<String>> {
+    let tokens = tokenize(text).map_err(|e| vec![e])?;
+
+    let mut parser = Parser {
+        tokens: &tokens,
+        position: 0,
+        reset_count: 0,
+        warnings: vec![],
+    };
+    let items = parser.items(false).map_err(|e| vec![e])?;
+
+    for warning in &parser.warnings {
+        println!("warning: {}", warning);
+    }
+
+    if parser.reset_count > 0 {
+        println!("warning: reset values of {} fields are not imported because register description doesn't have reset values", parser.reset_count);
+    }
+
+    let (root, root_name) = root_addrmap(&items)?;
+    let root_name = root_name.to_string();
+
+    let mut elaborator = Elaborator {
+        scopes: vec![Scope::new(&items)],
+        errors: vec![],
+    };
+    let registers = elaborator.block(root);
+
+    if !elaborator.errors.is_empty() {
+        return Err(elaborator.errors);
+    }
+
+    let mut groups: Vec<ImportedGroup> = vec![];
+    for ElaboratedRegister { path, mut register } in registers {
+        let (group_name, name_prefix) = match path.split_first() {
+            Some((group_name, name_prefix)) => (group_name.clone(), name_prefix),
+            None => (root_name.clone(), &[][..]),
+        };
+
+        if !name_prefix.is_empty() {
+            register.name = format!("{} {}", name_prefix.join(" "), register.name);
+        }
+
+        match groups.iter_mut().find(|g| g.name == group_name) {
+            Some(group) => group.registers.push(register),
+            None => groups.push(ImportedGroup { name: group_name, registers: vec![register] }),
+        }
+    }
+
+    Ok(ImportedFile {
+        name: root_name,
+        description: component_description(root),
+        default_register_size: None,
+        default_register_access: None,
+        groups,
+    })
+}
+
+/// The last named address map definition which is not instantiated in
+/// other components and the name of the definition.
+fn root_addrmap(items: &[Item]) -> Result<(&Component, &str), Vec<String>> {
+    fn instantiated_types<'a>(items: &'a [Item], types: &mut Vec<&'a str>) {
+        for item in items {
+            match item {
+                Item::Definition(c) | Item::Instances { component: ComponentRef::Anonymous(c), .. } => {
+                    instantiated_types(&c.items, types)
+                }
+                _ => (),
+            }
+            if let Item::Instances { component: ComponentRef::Type(name), .. } = item {
+                types.push(name);
+            }
+        }
+    }
+
+    let mut types = vec![];
+    instantiated_types(items, &mut types);
+
+    let roots: Vec<(&Component, &str)> = items.iter()
+        .filter_map(|item| match item {
+            Item::Definition(c) if c.kind == ComponentKind::Addrmap => c.type_name.as_deref().map(|name| (c, name)),
+            _ => None,
+        })
+        .filter(|(_, name)| !types.contains(name))
+        .collect();
+
+    match roots.split_last() {
+        Some((root, others)) => {
+            for (_, other) in others {
+                println!("warning: address map '{}' is not imported, only the last root address map '{}' is imported", other, root.1);
+            }
+            Ok(*root)
+        }
+        None => {
+            let anonymous_root = items.iter().any(|item| match item {
+                Item::Definition(c) | Item::Instances { component: ComponentRef::Anonymous(c), .. } => c.kind == ComponentKind::Addrmap,
+                _ => false,
+            });
+
+            if anonymous_root {
+                Err(vec!["root address map must be a named definition".to_string()])
+            } else {
+                Err(vec!["root address map definition not found".to_string()])
+            }
+        }
+    }
+}
+
+fn component_description(c: &Component) -> Option<String> {
+    let property = |name: &str| c.items.iter().find_map(|item| match item {
+        Item::Property { name: n, value: Value::Str(text), default: false } if n == name => Some(text.as_str()),
+        _ => None,
+    });
+
+    property("desc").or_else(|| property("name")).and_then(description)
+}
+
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = vec![];
+    let mut line = 1;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+
+        if c == '\n' {
+            line += 1;
+            i += 1;
+        } else if c.is_whitespace() {
+            i += 1;
+        } else if rest == "//" {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if rest == "/*" || rest == "<%" {
+            let end = if rest == "/*" { "*/" } else { "%>" };
+            if rest == "<%" {
+                println!("warning: line {}: embedded Perl is not supported and it is ignored", line);
+            }
+            i += 2;
+            while i < chars.len() && chars[i..chars.len().min(i + 2)].iter().collect::<String>() != end {
+                if chars[i] == '\n' {
+                    line += 1;
+                }
+                i += 1;
+            }
+            i += 2;
+        } else if c == '`' {
+            println!("warning: line {}: preprocessor directives are not supported and they are ignored", line);
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '"' {
+            let start_line = line;
+            let mut string = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err(format!("line {}: unterminated string", start_line)),
+                    Some('"') => break,
+                    Some('\\') if i + 1 < chars.len() => {
+                        string.push(chars[i + 1]);
+                        i += 1;
+                    }
+                    Some(c) => {
+                        if *c == '\n' {
+                            line += 1;
+                        }
+                        string.push(*c);
+                    }
+                }
+                i += 1;
+            }
+            i += 1;
+            tokens.push((Token::Str(string), start_line));
+        } else if c.is_ascii_digit() || c == '\'' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_' || chars[i] == '\'') {
+                i += 1;
+            }
+            let literal: String = chars[start..i].iter().collect();
+            match number(&literal) {
+                Some(value) => tokens.push((Token::Number(value), line)),
+                None => return Err(format!("line {}: invalid number '{}'", line, literal)),
+            }
+        } else if c.is_ascii_alphabetic() || c == '_' || c == '\\' {
+            // Identifiers starting with `\` are escaped keywords.
+            let start = if c == '\\' { i + 1 } else { i };
+            i += 1;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push((Token::Ident(chars[start..i].iter().collect()), line));
+        } else {
+            match PUNCTUATION.iter().find(|p| rest.starts_with(*p)) {
+                Some(p) => {
+                    tokens.push((Token::Punct(p), line));
+                    i += p.len();
+                }
+                None => return Err(format!("line {}: unexpected character '{}'", line, c)),
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Number in decimal, hexadecimal `0x1F` or Verilog style `8'h1F` format.
+fn number(literal: &str) -> Option<u64> {
+    let literal = literal.replace('_', "");
+
+    if let Some(i) = literal.find('\'') {
+        let digits = &literal[i + 1..];
+        let radix = match digits.chars().next()? {
+            'h' | 'H' => 16,
+            'd' | 'D' => 10,
+            'o' | 'O' => 8,
+            'b' | 'B' => 2,
+            _ => return None,
+        };
+        u64::from_str_radix(&digits[1..], radix).ok()
+    } else if let Some(hex) = literal.strip_prefix("0x").or_else(|| literal.strip_prefix("0X")) {
+        u64::from_str_radix(hex, 16).ok()
+    } else {
+        literal.parse().ok()
+    }
+}
+
+struct Parser<'a> {
+    tokens: &'a [(Token, usize)],
+    position: usize,
+    /// Count of fields which have reset values.
+    reset_count: usize,
+    warnings: Vec<String>,
+}
+
+impl <'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn peek_second(&self) -> Option<&'a Token> {
+        self.tokens.get(self.position + 1).map(|(token, _)| token)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens.get(self.position)
+            .or_else(|| self.tokens.last())
+            .map(|(_, line)| *line)
+            .unwrap_or(1)
+    }
+
+    fn next(&mut self) -> Result<&'a Token, String> {
+        match self.tokens.get(self.position) {
+            Some((token, _)) => {
+                self.position += 1;
+                Ok(token)
+            }
+            None => Err(format!("line {}: unexpected end of file", self.line())),
+        }
+    }
+
+    fn is_punct(&self, punct: &str) -> bool {
+        match self.peek() {
+            Some(Token::Punct(p)) => *p == punct,
+            _ => false,
+        }
+    }
+
+    fn expect_punct(&mut self, punct: &str) -> Result<(), String> {
+        let line = self.line();
+        match self.next()? {
+            Token::Punct(p) if *p == punct => Ok(()),
+            token => Err(format!("line {}: expected '{}', found {}", line, punct, token_text(token))),
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, String> {
+        let line = self.line();
+        match self.next()? {
+            Token::Ident(name) => Ok(name.clone()),
+            token => Err(format!("line {}: expected identifier, found {}", line, token_text(token))),
+        }
+    }
+
+    fn number(&mut self) -> Result<u64, String> {
+        let line = self.line();
+        match self.next()? {
+            Token::Number(value) => Ok(*value),
+            token => Err(format!("line {}: expected number, found {}", line, token_text(token))),
+        }
+    }
+
+    /// Statement like `instance.field->property = value;`.
+    fn is_dynamic_assignment(&self) -> bool {
+        self.tokens[self.position..].iter()
+            .map(|(token, _)| token)
+            .take_while(|token| **token != Token::Punct(";") && **token != Token::Punct("{"))
+            .any(|token| *token == Token::Punct("->"))
+    }
+
+    /// Skips tokens until `;` which is not inside braces.
+    fn skip_statement(&mut self) -> Result<(), String> {
+        let mut depth = 0;
+        loop {
+            match self.next()? {
+                Token::Punct("{") => depth += 1,
+                Token::Punct("}") => depth -= 1,
+                Token::Punct(";") if depth <= 0 => return Ok(()),
+                _ => (),
+            }
+        }
+    }
+
+    /// Items until end of file or until `}` if `block` is true.
+    fn items(&mut self, block: bool) -> Result<Vec<Item>, String> {
+        let mut items = vec![];
+
+        loop {
+            match self.peek() {
+                None if block => return Err(format!("line {}: expected '}}', found end of file", self.line())),
+                None => return Ok(items),
+                Some(Token::Punct("}")) if block => {
+                    self.position += 1;
+                    return Ok(items);
+                }
+                Some(_) => items.extend(self.item()?),
+            }
+        }
+    }
+
+    fn item(&mut self) -> Result<Option<Item>, String> {
+        let line = self.line();
+        let keyword = match self.peek() {
+            Some(Token::Punct(";")) => {
+                self.position += 1;
+                return Ok(None);
+            }
+            Some(Token::Ident(keyword)) => keyword.as_str(),
+            Some(token) => return Err(format!("line {}: unexpected {}", line, token_text(token))),
+            None => return Ok(None),
+        };
+
+        if let Some(kind) = ComponentKind::from_keyword(keyword) {
+            self.position += 1;
+            return self.component(kind);
+        }
+
+        match keyword {
+            "default" => {
+                self.position += 1;
+                let name = self.ident()?;
+                self.property(name, true)
+            }
+            "enum" => {
+                self.position += 1;
+                self.enum_definition().map(|e| Some(Item::Enum(e)))
+            }
+            "external" | "internal" => {
+                self.warnings.push(format!("line {}: '{}' is not supported and it is ignored", line, keyword));
+                self.position += 1;
+                self.item()
+            }
+            "signal" | "mem" | "constraint" | "property" | "struct" | "abstract" | "alias" => {
+                self.warnings.push(format!("line {}: '{}' is not supported and it is not imported", line, keyword));
+                self.skip_statement()?;
+                Ok(None)
+            }
+            _ if self.is_dynamic_assignment() => {
+                self.warnings.push(format!("line {}: dynamic property assignments are not supported and they are not imported", line));
+                self.skip_statement()?;
+                Ok(None)
+            }
+            _ => match self.peek_second() {
+                Some(Token::Punct("=")) | Some(Token::Punct(";")) => {
+                    let name = self.ident()?;
+                    self.property(name, false)
+                }
+                Some(Token::Punct("#")) => {
+                    self.warnings.push(format!("line {}: parameterized instance of '{}' is not supported and it is not imported", line, keyword));
+                    self.skip_statement()?;
+                    Ok(None)
+                }
+                Some(Token::Ident(_)) => {
+                    let type_name = self.ident()?;
+                    let instances = self.instances()?;
+                    Ok(Some(Item::Instances { component: ComponentRef::Type(type_name), instances, line }))
+                }
+                _ => Err(format!("line {}: unexpected identifier '{}'", line, keyword)),
+            },
+        }
+    }
+
+    /// Property assignment after the property name.
+    fn property(&mut self, name: String, default: bool) -> Result<Option<Item>, String> {
+        let line = self.line();
+
+        let value = if self.is_punct(";") {
+            Value::Ident("true".to_string())
+        } else {
+            self.expect_punct("=")?;
+            let value = match self.next()? {
+                Token::Number(value) => Value::Number(*value),
+                Token::Str(text) => Value::Str(text.clone()),
+                Token::Ident(value) => Value::Ident(value.clone()),
+                token => return Err(format!("line {}: unexpected {}", line, token_text(token))),
+            };
+
+            if !self.is_punct(";") {
+                self.warnings.push(format!("line {}: value of property '{}' is an expression which is not supported, property is not imported", line, name));
+                self.skip_statement()?;
+                return Ok(None);
+            }
+
+            value
+        };
+        self.expect_punct(";")?;
+
+        if name == "reset" && !default {
+            self.reset_count += 1;
+        }
+
+        if !SUPPORTED_PROPERTIES.contains(&name.as_str()) {
+            self.warnings.push(format!("line {}: property '{}' is not supported and it is not imported", line, name));
+            return Ok(None);
+        }
+
+        Ok(Some(Item::Property { name, value, default }))
+    }
+
+    /// Component definition after the component keyword.
+    fn component(&mut self, kind: ComponentKind) -> Result<Option<Item>, String> {
+        let line = self.line();
+
+        let type_name = match self.peek() {
+            Some(Token::Ident(_)) => Some(self.ident()?),
+            _ => None,
+        };
+
+        if self.is_punct("#") {
+            self.warnings.push(format!("line {}: component parameters are not supported and they are ignored", line));
+            self.position += 1;
+            self.expect_punct("(")?;
+            let mut depth = 1;
+            while depth > 0 {
+                match self.next()? {
+                    Token::Punct("(") => depth += 1,
+                    Token::Punct(")") => depth -= 1,
+                    _ => (),
+                }
+            }
+        }
+
+        self.expect_punct("{")?;
+        let items = self.items(true)?;
+        let component = Component { kind, type_name, items };
+
+        if self.is_punct(";") {
+            self.position += 1;
+            if component.type_name.is_none() {
+                self.warnings.push(format!("line {}: anonymous component does not have instances", line));
+            }
+            return Ok(Some(Item::Definition(component)));
+        }
+
+        let instances = self.instances()?;
+
+        Ok(Some(Item::Instances { component: ComponentRef::Anonymous(component), instances, line }))
+    }
+
+    /// Instances until `;`.
+    fn instances(&mut self) -> Result<Vec<Instance>, String> {
+        let mut instances = vec![];
+
+        loop {
+            let line = self.line();
+            let name = self.ident()?;
+            let mut instance = Instance { name, brackets: None, address: None, stride: None, alignment: None };
+
+            while self.is_punct("[") {
+                self.position += 1;
+                let first = self.number()?;
+                let brackets = if self.is_punct(":") {
+                    self.position += 1;
+                    Brackets::Range { msb: first, lsb: self.number()? }
+                } else {
+                    Brackets::Count(first)
+                };
+                self.expect_punct("]")?;
+
+                if instance.brackets.is_some() {
+                    self.warnings.push(format!("line {}: multidimensional arrays are not supported, only the first dimension of '{}' is imported", line, instance.name));
+                } else {
+                    instance.brackets = Some(brackets);
+                }
+            }
+
+            while let Some(Token::Punct(punct @ "=")) | Some(Token::Punct(punct @ "@")) |
+                      Some(Token::Punct(punct @ "+=")) | Some(Token::Punct(punct @ "%=")) = self.peek() {
+                self.position += 1;
+                let value = self.number()?;
+
+                match *punct {
+                    "=" => self.reset_count += 1,
+                    "@" => instance.address = Some(value),
+                    "+=" => instance.stride = Some(value),
+                    _ => instance.alignment = Some(value),
+                }
+            }
+
+            instances.push(instance);
+
+            if self.is_punct(",") {
+                self.position += 1;
+            } else {
+                self.expect_punct(";")?;
+                return Ok(instances);
+            }
+        }
+    }
+
+    /// Enum definition after keyword `enum`.
+    fn enum_definition(&mut self) -> Result<EnumDefinition, String> {
+        let name = self.ident()?;
+        self.expect_punct("{")?;
+
+        let mut entries: Vec<EnumEntry> = vec![];
+        while !self.is_punct("}") {
+            let entry_name = self.ident()?;
+            let value = if self.is_punct("=") {
+                self.position += 1;
+                self.number()?
+            } else {
+                entries.last().map(|e| e.value + 1).unwrap_or(0)
+            };
+
+            let mut entry_description = None;
+            let mut display_name = None;
+            if self.is_punct("{") {
+                self.position += 1;
+                for item in self.items(true)? {
+                    match item {
+                        Item::Property { name, value: Value::Str(text), .. } if name == "desc" => entry_description = Some(text),
+                        Item::Property { name, value: Value::Str(text), .. } if name == "name" => display_name = Some(text),
+                        _ => (),
+                    }
+                }
+            }
+            self.expect_punct(";")?;
+
+            entries.push(EnumEntry {
+                name: entry_name,
+                value,
+                description: entry_description.or(display_name).and_then(|text| description(&text)),
+            });
+        }
+
+        self.expect_punct("}")?;
+        self.expect_punct(";")?;
+
+        Ok(EnumDefinition { name, entries })
+    }
+}
+
+fn token_text(token: &Token) -> String {
+    match token {
+        Token::Ident(name) => format!("identifier '{}'", name),
+        Token::Number(value) => format!("number '{}'", value),
+        Token::Str(text) => format!("string \"{}\"", text),
+        Token::Punct(punct) => format!("'{}'", punct),
+    }
+}
+
+/// Named component and enum definitions and default
+/// property values of one component body.
+struct Scope<'a> {
+    definitions: HashMap<&'a str, &'a Component>,
+    enums: HashMap<&'a str, &'a EnumDefinition>,
+    defaults: HashMap<&'a str, &'a Value>,
+}
+
+impl <'a> Scope<'a> {
+    fn new(items: &'a [Item]) -> Self {
+        let mut scope = Scope {
+            definitions: HashMap::new(),
+            enums: HashMap::new(),
+            defaults: HashMap::new(),
+        };
+
+        for item in items {
+            match item {
+                Item::Definition(c) | Item::Instances { component: ComponentRef::Anonymous(c), .. } => {
+                    if let Some(name) = &c.type_name {
+                        scope.definitions.insert(name, c);
+                    }
+                }
+                Item::Enum(e) => {
+                    scope.enums.insert(&e.name, e);
+                }
+                Item::Property { name, value, default: true } => {
+                    scope.defaults.insert(name, value);
+                }
+                _ => (),
+            }
+        }
+
+        scope
+    }
+}
+
+struct ElaboratedRegister {
+    /// Names of `regfile` and `addrmap` instances from the root address map.
+    path: Vec<String>,
+    register: ImportedRegister,
+}
+
+/// Scopes are lexical, so default values, definitions and enums are
+/// looked up from the scopes where the component is defined. Scope depth
+/// is the count of scopes in the scope stack which are visible at the
+/// component definition.
+struct Elaborator<'a> {
+    scopes: Vec<Scope<'a>>,
+    errors: Vec<String>,
+}
+
+impl <'a> Elaborator<'a> {
+    /// Component of the instances and scope depth of the component definition.
+    fn component(&self, component_ref: &'a ComponentRef) -> Result<(&'a Component, usize), &'a str> {
+        match component_ref {
+            ComponentRef::Anonymous(component) => Ok((component, self.scopes.len())),
+            ComponentRef::Type(type_name) => self.scopes.iter()
+                .enumerate()
+                .rev()
+                .find_map(|(i, scope)| scope.definitions.get(type_name.as_str()).map(|c| (*c, i + 1)))
+                .ok_or(type_name.as_str()),
+        }
+    }
+
+    /// Runs `f` with scopes visible at scope depth `depth`.
+    fn in_scope<T, F: FnOnce(&mut Self) -> T>(&mut self, depth: usize, f: F) -> T {
+        let inner_scopes = self.scopes.split_off(depth);
+        let result = f(self);
+        self.scopes.extend(inner_scopes);
+        result
+    }
+
+    /// Value of property which is assigned in the component
+    /// or which has a default value in scopes until scope depth `depth`.
+    fn property(&self, c: &'a Component, name: &str, depth: usize) -> Option<&'a Value> {
+        let assigned = c.items.iter().find_map(|item| match item {
+            Item::Property { name: n, value, default: false } if n == name => Some(value),
+            _ => None,
+        });
+
+        assigned.or_else(|| self.scopes[..depth].iter().rev().find_map(|scope| scope.defaults.get(name).copied()))
+    }
+
+    fn number_property(&mut self, c: &'a Component, name: &str, default_value: u64, depth: usize) -> u64 {
+        match self.property(c, name, depth) {
+            Some(Value::Number(value)) => *value,
+            Some(_) => {
+                self.errors.push(format!("value of property '{}' is not a number", name));
+                default_value
+            }
+            None => default_value,
+        }
+    }
+
+    /// Registers of `addrmap` or `regfile` component.
+    /// Register locations start from zero.
+    fn block(&mut self, c: &'a Component) -> Vec<ElaboratedRegister> {
+        self.scopes.push(Scope::new(&c.items));
+
+        let mut registers = vec![];
+        let mut next_address = 0;
+
+        for item in &c.items {
+            let (component_ref, instances, line) = match item {
+                Item::Instances { component, instances, line } => (component, instances, *line),
+                _ => continue,
+            };
+
+            let (component, depth) = match self.component(component_ref) {
+                Ok(component) => component,
+                Err(type_name) => {
+                    self.errors.push(format!("line {}: component '{}' is not defined", line, type_name));
+                    continue;
+                }
+            };
+
+            for instance in instances {
+                // Elements of the instance are created at address
+                // zero and then moved to the right address.
+                let (element, size) = match component.kind {
+                    ComponentKind::Reg => match self.in_scope(depth, |e| e.register(component, &instance.name)) {
+                        Some(r) => {
+                            let size = r.size as u64 / 8;
+                            (vec![ElaboratedRegister { path: vec![], register: r }], size)
+                        }
+                        None => continue,
+                    },
+                    ComponentKind::Regfile | ComponentKind::Addrmap => {
+                        let mut element = self.in_scope(depth, |e| e.block(component));
+                        let size = element.iter()
+                            .map(|r| relative_address(&r.register) + r.register.size as u64 / 8)
+                            .max()
+                            .unwrap_or(0);
+                        for r in &mut element {
+                            r.path.insert(0, instance.name.clone());
+                        }
+                        (element, size)
+                    }
+                    ComponentKind::Field => {
+                        self.errors.push(format!("line {}: field '{}' is not inside a register", line, instance.name));
+                        continue;
+                    }
+                };
+
+                let count = match instance.brackets {
+                    None => None,
+                    Some(Brackets::Count(count)) => Some(count),
+                    Some(Brackets::Range { .. }) => {
+                        self.errors.push(format!("line {}: bit range is not allowed for instance '{}'", line, instance.name));
+                        continue;
+                    }
+                };
+
+                let alignment = instance.alignment.unwrap_or_else(|| size.max(1).next_power_of_two());
+                let address = instance.address.unwrap_or_else(|| align(next_address, alignment));
+                let stride = instance.stride.unwrap_or(size);
+
+                let elements: Vec<(Option<u64>, u64)> = match count {
+                    None => vec![(None, address)],
+                    Some(count) => (0..count).map(|i| (Some(i), address + i * stride)).collect(),
+                };
+
+                for (index, element_address) in elements {
+                    for r in &element {
+                        let mut r = ElaboratedRegister {
+                            path: r.path.clone(),
+                            register: r.register.clone(),
+                        };
+
+                        // Array index is added to the instance name.
+                        if let Some(index) = index {
+                            match r.path.first_mut() {
+                                Some(name) => *name = format!("{}{}", name, index),
+                                None => r.register.name = format!("{}{}", r.register.name, index),
+                            }
+                        }
+
+                        r.register.location = RegisterLocation::Relative(element_address + relative_address(&r.register));
+                        registers.push(r);
+                    }
+
+                    next_address = next_address.max(element_address + size);
+                }
+            }
+        }
+
+        self.scopes.pop();
+
+        registers
+    }
+
+    fn register(&mut self, c: &'a Component, name: &str) -> Option<ImportedRegister> {
+        self.scopes.push(Scope::new(&c.items));
+
+        let regwidth = self.number_property(c, "regwidth", DEFAULT_REGWIDTH, self.scopes.len());
+        let size = match regwidth {
+            8 => RegisterSize::Size8,
+            16 => RegisterSize::Size16,
+            32 => RegisterSize::Size32,
+            64 => RegisterSize::Size64,
+            _ => {
+                self.errors.push(format!("register '{}' has unsupported width {}, supported register widths are 8, 16, 32 and 64", name, regwidth));
+                self.scopes.pop();
+                return None;
+            }
+        };
+
+        let mut fields = vec![];
+        let mut enums = vec![];
+        let mut field_access = vec![];
+        let mut next_lsb = 0;
+
+        for item in &c.items {
+            let (component_ref, instances, line) = match item {
+                Item::Instances { component, instances, line } => (component, instances, *line),
+                _ => continue,
+            };
+
+            let (component, depth) = match self.component(component_ref) {
+                Ok(component) => component,
+                Err(type_name) => {
+                    self.errors.push(format!("line {}: field '{}' is not defined", line, type_name));
+                    continue;
+                }
+            };
+
+            if component.kind != ComponentKind::Field {
+                self.errors.push(format!("line {}: register '{}' can only contain fields", line, name));
+                continue;
+            }
+
+            for instance in instances {
+                let (msb, lsb) = match instance.brackets {
+                    Some(Brackets::Range { msb, lsb }) => (msb.max(lsb), msb.min(lsb)),
+                    Some(Brackets::Count(width)) => {
+                        let lsb = instance.address.unwrap_or(next_lsb);
+                        (lsb + width.max(1) - 1, lsb)
+                    }
+                    None => {
+                        let width = self.number_property(component, "fieldwidth", DEFAULT_FIELDWIDTH, depth);
+                        let lsb = instance.address.unwrap_or(next_lsb);
+                        (lsb + width.max(1) - 1, lsb)
+                    }
+                };
+                next_lsb = msb + 1;
+
+                if msb > u64::from(u16::MAX) {
+                    self.errors.push(format!("line {}: field '{}' has invalid bit range", line, instance.name));
+                    continue;
+                }
+                let range = BitRange::new(msb as u16, lsb as u16);
+
+                let access = match self.property(component, "sw", depth) {
+                    None => Some(AccessMode::ReadWrite),
+                    Some(Value::Ident(access)) => match access.as_str() {
+                        "rw" | "wr" => Some(AccessMode::ReadWrite),
+                        "r" => Some(AccessMode::Read),
+                        "w" | "w1" => Some(AccessMode::Write),
+                        "rw1" | "w1r" => Some(AccessMode::ReadWrite),
+                        "na" => None,
+                        unknown => {
+                            self.errors.push(format!("line {}: field '{}' has unknown sw access '{}'", line, instance.name, unknown));
+                            continue;
+                        }
+                    },
+                    Some(_) => {
+                        self.errors.push(format!("line {}: field '{}' has invalid sw access", line, instance.name));
+                        continue;
+                    }
+                };
+
+                // Fields which software can't access are reserved.
+                let access = match access {
+                    Some(access) => access,
+                    None => {
+                        fields.push(ImportedField { range, name: None, description: None });
+                        continue;
+                    }
+                };
+                field_access.push(access);
+
+                if let Some(Value::Ident(enum_name)) = self.property(component, "encode", depth) {
+                    // Enums can also be defined in the field body.
+                    let field_enum = component.items.iter().find_map(|item| match item {
+                        Item::Enum(e) if e.name == *enum_name => Some(e),
+                        _ => None,
+                    });
+                    let field_enum = field_enum.or_else(|| {
+                        self.scopes[..depth].iter().rev().find_map(|scope| scope.enums.get(enum_name.as_str()).copied())
+                    });
+
+                    match field_enum {
+                        Some(e) => enums.push(ImportedEnum {
+                            name: e.name.clone(),
+                            description: None,
+                            range,
+                            values: e.entries.iter().map(|entry| ImportedEnumValue {
+                                value: entry.value,
+                                name: entry.name.clone(),
+                                description: entry.description.clone(),
+                            }).collect(),
+                        }),
+                        None => self.errors.push(format!("line {}: enum '{}' of field '{}' is not defined", line, enum_name, instance.name)),
+                    }
+                }
+
+                fields.push(ImportedField {
+                    range,
+                    name: Some(instance.name.clone()),
+                    description: component_description(component),
+                });
+            }
+        }
+
+        self.scopes.pop();
+
+        let access = if !field_access.is_empty() && field_access.iter().all(|a| *a == AccessMode::Read) {
+            AccessMode::Read
+        } else if !field_access.is_empty() && field_access.iter().all(|a| *a == AccessMode::Write) {
+            AccessMode::Write
+        } else {
+            AccessMode::ReadWrite
+        };
+
+        Some(ImportedRegister {
+            name: name.to_string(),
+            description: component_description(c),
+            access,
+            size,
+            location: RegisterLocation::Relative(0),
+            write_location: None,
+            fields,
+            enums,
+        })
+    }
+}
+
+fn relative_address(r: &ImportedRegister) -> u64 {
+    match r.location {
+        RegisterLocation::Index(address) |
+        RegisterLocation::Relative(address) |
+        RegisterLocation::Absolute(address) => address,
+    }
+}
+
+fn align(address: u64, alignment: u64) -> u64 {
+    match address % alignment {
+        0 => address,
+        remainder => address + alignment - remainder,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parser_warnings(text: &str) -> Vec<String> {
+        let tokens = tokenize(text).unwrap();
+        let mut parser = Parser { tokens: &tokens, position: 0, reset_count: 0, warnings: vec![] };
+        parser.items(false).unwrap();
+        parser.warnings
+    }
+
+    fn register<'a>(file: &'a ImportedFile, name: &str) -> &'a ImportedRegister {
+        file.groups.iter()
+            .flat_map(|g| g.registers.iter())
+            .find(|r| r.name == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn anonymous_root_addrmap_is_error() {
+        let errors = rdl_to_imported_file("addrmap { reg { field {} a; } r; };").err().unwrap();
+        assert_eq!(errors, vec!["root address map must be a named definition".to_string()]);
+    }
+
+    #[test]
+    fn array_is_placed_with_regalign() {
+        let file = rdl_to_imported_file("
+            addrmap top {
+                reg { regwidth = 8; field {} a; } status;
+                reg { field {} a; } data[2];
+                reg { field {} a; } ctrl[2] += 0x10;
+            };
+        ").unwrap();
+
+        assert_eq!(file.name, "top");
+        assert_eq!(file.groups.len(), 1);
+        assert_eq!(file.groups[0].name, "top");
+        assert_eq!(register(&file, "status").location, RegisterLocation::Relative(0x0));
+        assert_eq!(register(&file, "data0").location, RegisterLocation::Relative(0x4));
+        assert_eq!(register(&file, "data1").location, RegisterLocation::Relative(0x8));
+        assert_eq!(register(&file, "ctrl0").location, RegisterLocation::Relative(0xC));
+        assert_eq!(register(&file, "ctrl1").location, RegisterLocation::Relative(0x1C));
+    }
+
+    #[test]
+    fn defaults_are_lexically_scoped() {
+        let file = rdl_to_imported_file("
+            reg ctrl_t { field {} a; };
+            addrmap chip {
+                default regwidth = 8;
+                ctrl_t top_ctrl;
+                reg { field {} a; } status;
+            };
+        ").unwrap();
+
+        assert_eq!(register(&file, "top_ctrl").size, RegisterSize::Size32);
+        assert_eq!(register(&file, "status").size, RegisterSize::Size8);
+    }
+
+    #[test]
+    fn encode_enum_is_imported() {
+        let file = rdl_to_imported_file("
+            enum mode_e {
+                off = 0 { desc = \"Disabled\"; };
+                on = 2'h3;
+            };
+            addrmap top {
+                reg { field { encode = mode_e; } mode[4:3]; } ctrl;
+            };
+        ").unwrap();
+
+        let ctrl = register(&file, "ctrl");
+        assert_eq!(ctrl.enums.len(), 1);
+        let e = &ctrl.enums[0];
+        assert_eq!(e.name, "mode_e");
+        assert_eq!(e.range, BitRange::new(4, 3));
+        assert_eq!(e.values.len(), 2);
+        assert_eq!((e.values[0].value, e.values[0].name.as_str()), (0, "off"));
+        assert_eq!(e.values[0].description.as_deref(), Some("Disabled"));
+        assert_eq!((e.values[1].value, e.values[1].name.as_str()), (3, "on"));
+    }
+
+    #[test]
+    fn unsupported_property_is_warning() {
+        let warnings = parser_warnings("
+            addrmap top {
+                reg {
+                    field { onread = rclr; } a;
+                } r;
+            };
+        ");
+
+        assert_eq!(warnings, vec!["line 4: property 'onread' is not supported and it is not imported".to_string()]);
+    }
+    #[test]
+    fn alias_is_warning() {
+        let file = rdl_to_imported_file("
+            reg ctrl_t { field {} a; };
+            addrmap chip {
+                ctrl_t top_ctrl;
+                alias top_ctrl ctrl_t top_alias;
+            };
+        ").unwrap();
+
+        assert_eq!(file.groups[0].registers.len(), 1);
+    }
+}
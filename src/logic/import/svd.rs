@@ -0,0 +1,433 @@
+//! CMSIS-SVD device description import.
+//!
+//! Peripherals are register groups and register locations are absolute
+//! addresses. Registers of clusters are added to the peripheral of the
+//! cluster with cluster name as a name prefix. Register and cluster arrays
+//! (`dim` elements) are expanded to separate registers.
+//!
+//! Constructs which the register description format can't express are
+//! reported as warnings and left out from the imported file.
+
+use std::convert::TryFrom;
+
+use roxmltree::{Document, Node};
+
+use crate::logic::validation::register::{
+    AccessMode,
+    BitRange,
+    RegisterLocation,
+    RegisterSize,
+};
+
+use super::{
+    ImportedFile,
+    ImportedGroup,
+    ImportedRegister,
+    ImportedField,
+    ImportedEnum,
+    ImportedEnumValue,
+    child,
+    children,
+    child_text,
+    description,
+};
+
+/// Register size and access which registers inherit from
+/// device, peripheral and cluster elements.
+#[derive(Clone, Copy, Default)]
+struct RegisterProperties {
+    size: Option<RegisterSize>,
+    access: Option<AccessMode>,
+}
+
+struct SvdImporter<'a, 'input> {
+    document: &'a Document<'input>,
+    errors: Vec<String>,
+}
+
+pub fn svd_to_imported_file(text: &str) -> Result<ImportedFile, Vec<String>> {
+    let document = Document::parse(text).map_err(|e| vec![format!("XML parsing failed: {}", e)])?;
+    let device = document.root_element();
+
+    if device.tag_name().name() != "device" {
+        return Err(vec![format!("expected root element 'device', found '{}'", device.tag_name().name())]);
+    }
+
+    let mut importer = SvdImporter {
+        document: &document,
+        errors: vec![],
+    };
+
+    let name = importer.required_text(device, "name", "device").unwrap_or("device").to_string();
+    let properties = importer.register_properties(device, RegisterProperties::default(), &name);
+
+    let mut groups = vec![];
+    if let Some(peripherals) = child(device, "peripherals") {
+        for peripheral in children(peripherals, "peripheral") {
+            if let Some(group) = importer.peripheral(peripheral, properties) {
+                groups.push(group);
+            }
+        }
+    }
+
+    if !importer.errors.is_empty() {
+        return Err(importer.errors);
+    }
+
+    Ok(ImportedFile {
+        name,
+        description: child_text(device, "description").and_then(description),
+        default_register_size: properties.size,
+        default_register_access: properties.access,
+        groups,
+    })
+}
+
+impl <'a, 'input> SvdImporter<'a, 'input> {
+    fn peripheral(&mut self, peripheral: Node<'a, 'input>, properties: RegisterProperties) -> Option<ImportedGroup> {
+        let name = self.required_text(peripheral, "name", "peripheral")?.to_string();
+        let context = format!("peripheral '{}'", name);
+
+        if child(peripheral, "dim").is_some() {
+            println!("warning: {} is a peripheral array, only the first peripheral is imported", context);
+        }
+
+        // Derived peripherals inherit elements which they don't define.
+        let base = match peripheral.attribute("derivedFrom") {
+            Some(base_name) => {
+                let base = self.document.descendants()
+                    .find(|n| n.has_tag_name("peripheral") && child_text(*n, "name") == Some(base_name));
+                if base.is_none() {
+                    self.errors.push(format!("{} is derived from unknown peripheral '{}'", context, base_name));
+                    return None;
+                }
+                base
+            }
+            None => None,
+        };
+
+        let element = |name: &str| child(peripheral, name).or_else(|| base.and_then(|base| child(base, name)));
+
+        let base_address = match element("baseAddress") {
+            Some(node) => self.integer(node.text().unwrap_or(""), &context)?,
+            None => {
+                self.errors.push(format!("{} does not have element 'baseAddress'", context));
+                return None;
+            }
+        };
+
+        let mut properties = match base {
+            Some(base) => self.register_properties(base, properties, &context),
+            None => properties,
+        };
+        properties = self.register_properties(peripheral, properties, &context);
+
+        let mut registers = vec![];
+        if let Some(node) = element("registers") {
+            self.registers(node, "", base_address, properties, &mut registers);
+        }
+
+        Some(ImportedGroup {
+            name,
+            registers,
+        })
+    }
+
+    /// Registers and clusters of element `parent`.
+    fn registers(&mut self, parent: Node<'a, 'input>, name_prefix: &str, address: u64, properties: RegisterProperties, registers: &mut Vec<ImportedRegister>) {
+        for node in parent.children().filter(|n| n.is_element()) {
+            match node.tag_name().name() {
+                "register" => self.register(node, name_prefix, address, properties, registers),
+                "cluster" => self.cluster(node, name_prefix, address, properties, registers),
+                _ => (),
+            }
+        }
+    }
+
+    fn cluster(&mut self, cluster: Node<'a, 'input>, name_prefix: &str, address: u64, properties: RegisterProperties, registers: &mut Vec<ImportedRegister>) {
+        let context = format!("cluster '{}{}'", name_prefix, child_text(cluster, "name").unwrap_or(""));
+
+        if cluster.has_attribute("derivedFrom") {
+            println!("warning: {} attribute 'derivedFrom' is not supported, only registers defined in the cluster are imported", context);
+        }
+
+        let properties = self.register_properties(cluster, properties, &context);
+
+        for (name, offset) in self.instances(cluster, &context) {
+            let name_prefix = format!("{}{} ", name_prefix, name);
+            self.registers(cluster, &name_prefix, address + offset, properties, registers);
+        }
+    }
+
+    fn register(&mut self, register: Node<'a, 'input>, name_prefix: &str, address: u64, properties: RegisterProperties, registers: &mut Vec<ImportedRegister>) {
+        let context = format!("register '{}{}'", name_prefix, child_text(register, "name").unwrap_or(""));
+
+        if register.has_attribute("derivedFrom") {
+            println!("warning: {} attribute 'derivedFrom' is not supported, only fields defined in the register are imported", context);
+        }
+
+        let properties = self.register_properties(register, properties, &context);
+        let size = properties.size.unwrap_or(RegisterSize::Size32);
+        let access = properties.access.unwrap_or(AccessMode::ReadWrite);
+
+        let mut fields = vec![];
+        let mut enums = vec![];
+        if let Some(node) = child(register, "fields") {
+            for field in children(node, "field") {
+                self.field(field, &context, &mut fields, &mut enums);
+            }
+        }
+
+        for (name, offset) in self.instances(register, &context) {
+            registers.push(ImportedRegister {
+                name: format!("{}{}", name_prefix, name),
+                description: child_text(register, "description").and_then(description),
+                access,
+                size,
+                location: RegisterLocation::Absolute(address + offset),
+                write_location: None,
+                fields: fields.clone(),
+                enums: enums.clone(),
+            });
+        }
+    }
+
+    fn field(&mut self, field: Node<'a, 'input>, register_context: &str, fields: &mut Vec<ImportedField>, enums: &mut Vec<ImportedEnum>) {
+        let name = match self.required_text(field, "name", register_context) {
+            Some(name) => name.to_string(),
+            None => return,
+        };
+        let context = format!("field '{}' of {}", name, register_context);
+
+        if child(field, "dim").is_some() {
+            println!("warning: {} is a field array, only the first field is imported", context);
+        }
+
+        let range = match self.bit_range(field, &context) {
+            Some(range) => range,
+            None => return,
+        };
+
+        let mut enumerated_values = children(field, "enumeratedValues");
+        if let Some(values) = enumerated_values.next() {
+            if let Some(e) = self.enumerated_values(values, &name, range, &context) {
+                enums.push(e);
+            }
+        }
+        if enumerated_values.next().is_some() {
+            println!("warning: {} has more than one element 'enumeratedValues', only the first one is imported", context);
+        }
+
+        fields.push(ImportedField {
+            range,
+            name: Some(name),
+            description: child_text(field, "description").and_then(description),
+        });
+    }
+
+    /// Bit range from `bitOffset` and `bitWidth`, `lsb` and `msb` or `bitRange`.
+    fn bit_range(&mut self, field: Node<'a, 'input>, context: &str) -> Option<BitRange> {
+        let (msb, lsb) = if let Some(offset) = child_text(field, "bitOffset") {
+            let lsb = self.integer(offset, context)?;
+            let width = match child_text(field, "bitWidth") {
+                Some(width) => self.integer(width, context)?,
+                None => 1,
+            };
+            (lsb + width.max(1) - 1, lsb)
+        } else if let (Some(lsb), Some(msb)) = (child_text(field, "lsb"), child_text(field, "msb")) {
+            (self.integer(msb, context)?, self.integer(lsb, context)?)
+        } else if let Some(range) = child_text(field, "bitRange") {
+            let bits: Vec<&str> = range.trim_start_matches('[').trim_end_matches(']').split(':').collect();
+            match bits.as_slice() {
+                [msb, lsb] => (self.integer(msb, context)?, self.integer(lsb, context)?),
+                _ => {
+                    self.errors.push(format!("{} has invalid bit range '{}'", context, range));
+                    return None;
+                }
+            }
+        } else {
+            self.errors.push(format!("{} does not have bit range", context));
+            return None;
+        };
+
+        if msb < lsb || msb > u64::from(u16::MAX) {
+            self.errors.push(format!("{} has invalid bit range, msb: {}, lsb: {}", context, msb, lsb));
+            return None;
+        }
+
+        Some(BitRange::new(msb as u16, lsb as u16))
+    }
+
+    fn enumerated_values(&mut self, values: Node<'a, 'input>, field_name: &str, range: BitRange, context: &str) -> Option<ImportedEnum> {
+        // Derived enumerated values don't inherit the name, so that
+        // enums of the same register get different names.
+        let enum_name = child_text(values, "name").unwrap_or(field_name).to_string();
+        let values = match values.attribute("derivedFrom") {
+            Some(base_name) => {
+                // Base name can be a path like `peripheral.register.field.values`.
+                let base_name = base_name.rsplit('.').next().unwrap_or(base_name);
+                let base = self.document.descendants()
+                    .find(|n| n.has_tag_name("enumeratedValues") && child_text(*n, "name") == Some(base_name));
+                match base {
+                    Some(base) => base,
+                    None => {
+                        println!("warning: {} enumerated values are derived from unknown enumerated values '{}', enum is not imported", context, base_name);
+                        return None;
+                    }
+                }
+            }
+            None => values,
+        };
+
+        let mut enum_values = vec![];
+        for value in children(values, "enumeratedValue") {
+            let name = match self.required_text(value, "name", context) {
+                Some(name) => name.to_string(),
+                None => continue,
+            };
+
+            let number = match child_text(value, "value") {
+                Some(number) if number.contains(['x', 'X']) && number.starts_with('#') => {
+                    println!("warning: enumerated value '{}' of {} has don't care bits, value is not imported", name, context);
+                    continue;
+                }
+                Some(number) => match self.integer(number, context) {
+                    Some(number) => number,
+                    None => continue,
+                },
+                None => {
+                    println!("warning: enumerated value '{}' of {} is a default value, value is not imported", name, context);
+                    continue;
+                }
+            };
+
+            enum_values.push(ImportedEnumValue {
+                value: number,
+                name,
+                description: child_text(value, "description").and_then(description),
+            });
+        }
+
+        if enum_values.is_empty() {
+            return None;
+        }
+
+        Some(ImportedEnum {
+            name: enum_name,
+            description: None,
+            range,
+            values: enum_values,
+        })
+    }
+
+    /// Names and address offsets of array elements. Elements which
+    /// are not arrays have only one instance.
+    fn instances(&mut self, node: Node<'a, 'input>, context: &str) -> Vec<(String, u64)> {
+        let name = child_text(node, "name").unwrap_or("");
+        let offset = match child_text(node, "addressOffset") {
+            Some(offset) => self.integer(offset, context).unwrap_or(0),
+            None => {
+                self.errors.push(format!("{} does not have element 'addressOffset'", context));
+                0
+            }
+        };
+
+        let count = match child_text(node, "dim") {
+            Some(count) => match self.integer(count, context) {
+                Some(count) => count,
+                None => return vec![],
+            },
+            None => return vec![(name.to_string(), offset)],
+        };
+        let increment = match child_text(node, "dimIncrement") {
+            Some(increment) => self.integer(increment, context).unwrap_or(0),
+            None => 0,
+        };
+
+        let indexes: Vec<String> = match child_text(node, "dimIndex") {
+            Some(indexes) => match indexes.split('-').collect::<Vec<&str>>().as_slice() {
+                [first, last] if first.parse::<u64>().is_ok() && last.parse::<u64>().is_ok() => {
+                    (first.parse::<u64>().unwrap()..=last.parse::<u64>().unwrap()).map(|i| i.to_string()).collect()
+                }
+                _ => indexes.split(',').map(|i| i.trim().to_string()).collect(),
+            },
+            None => (0..count).map(|i| i.to_string()).collect(),
+        };
+
+        if indexes.len() as u64 != count {
+            self.errors.push(format!("{} element 'dimIndex' has {} indexes but element 'dim' is {}", context, indexes.len(), count));
+            return vec![];
+        }
+
+        indexes.iter().enumerate().map(|(i, index)| {
+            let name = name.replace("[%s]", index).replace("%s", index);
+            (name, offset + i as u64 * increment)
+        }).collect()
+    }
+
+    fn register_properties(&mut self, node: Node<'a, 'input>, inherited: RegisterProperties, context: &str) -> RegisterProperties {
+        let mut properties = inherited;
+
+        if let Some(size) = child_text(node, "size") {
+            match self.integer(size, context).map(|size| RegisterSize::try_from(size.to_string().as_str())) {
+                Some(Ok(size)) => properties.size = Some(size),
+                Some(Err(e)) => self.errors.push(format!("{} has {}", context, e)),
+                None => (),
+            }
+        }
+
+        if let Some(access) = child_text(node, "access") {
+            match svd_access(access) {
+                Some(access) => properties.access = Some(access),
+                None => self.errors.push(format!("{} has unknown access '{}'", context, access)),
+            }
+        }
+
+        properties
+    }
+
+    fn required_text(&mut self, node: Node<'a, 'input>, name: &str, context: &str) -> Option<&'a str> {
+        let text = child_text(node, name);
+        if text.is_none() {
+            self.errors.push(format!("element '{}' is missing from element '{}' of {}", name, node.tag_name().name(), context));
+        }
+        text
+    }
+
+    /// Integer with optional prefix `0x` or `#` (binary) and
+    /// optional multiplier postfix `k`, `M` or `G`.
+    fn integer(&mut self, text: &str, context: &str) -> Option<u64> {
+        let text = text.trim();
+        let (text, multiplier) = match text.chars().last() {
+            Some('k') | Some('K') => (&text[..text.len() - 1], 1 << 10),
+            Some('m') | Some('M') => (&text[..text.len() - 1], 1 << 20),
+            Some('g') | Some('G') => (&text[..text.len() - 1], 1 << 30),
+            _ => (text, 1),
+        };
+
+        let value = if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+            u64::from_str_radix(hex, 16)
+        } else if let Some(binary) = text.strip_prefix('#').or_else(|| text.strip_prefix("0b")) {
+            u64::from_str_radix(binary, 2)
+        } else {
+            text.parse::<u64>()
+        };
+
+        match value.ok().and_then(|value| value.checked_mul(multiplier)) {
+            Some(value) => Some(value),
+            None => {
+                self.errors.push(format!("{} has invalid integer '{}'", context, text));
+                None
+            }
+        }
+    }
+}
+
+fn svd_access(access: &str) -> Option<AccessMode> {
+    match access {
+        "read-only" => Some(AccessMode::Read),
+        "write-only" | "writeOnce" => Some(AccessMode::Write),
+        "read-write" | "read-writeOnce" => Some(AccessMode::ReadWrite),
+        _ => None,
+    }
+}
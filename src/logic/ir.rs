@@ -0,0 +1,256 @@
+//! Intermediate representation (IR) of a validated register description.
+//!
+//! External code generators receive the IR as JSON and subcommand `export`
+//! writes it as JSON or YAML. Default values from
+//! the register description are already applied to every register, so
+//! consumers don't have to know the validation rules of the TOML format.
+//!
+//! # Format
+//!
+//! Version 1 of the IR has the following structure. Optional values are
+//! `null` when they are missing.
+//!
+//! ```text
+//! {
+//!   "ir_version": 1,
+//!   "description": {
+//!     "name": string,
+//!     "description": string | null,
+//!     "version": string,               // register description specification version
+//!     "extension": "vga" | null,
+//!     "index_size": 8 | 16 | 32 | 64,
+//!     "address_size": 8 | 16 | 32 | 64 | null,   // null is pointer size
+//!   },
+//!   "groups": [
+//!     {
+//!       "name": string | null,         // null if registers are not grouped
+//!       "registers": [
+//!         {
+//!           "name": string,
+//!           "description": string | null,
+//!           "access": "r" | "w" | "rw",
+//!           "size": 8 | 16 | 32 | 64,
+//!           "read_location": { "kind": "index" | "relative" | "absolute", "value": integer },
+//!           "write_location": { "kind": "index" | "relative" | "absolute", "value": integer },
+//!           "restore_order": integer | null,
+//!           "bit_fields": [
+//!             { "msb": integer, "lsb": integer, "reserved": bool, "name": string | null, "description": string | null }
+//!           ],
+//!           "enums": [
+//!             {
+//!               "name": string,
+//!               "description": string | null,
+//!               "msb": integer,
+//!               "lsb": integer,
+//!               "all_possible_values_are_defined": bool,
+//!               "values": [ { "value": integer, "name": string, "description": string | null } ]
+//!             }
+//!           ]
+//!         }
+//!       ]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Bit fields are in the same order as in the register description file.
+//! Fields `read_location` and `write_location` are equal if the register
+//! doesn't have a separate write location.
+//!
+//! The IR version is incremented when a change breaks existing consumers.
+//! Adding new keys is not a breaking change.
+
+use serde::Serialize;
+
+use super::validation::{
+    ParsedFile,
+    register::{
+        Register,
+        RegisterLocation,
+        RegisterFunction,
+        RegisterEnum,
+        RegisterEnumValue,
+    },
+    register_description::{
+        RegisterDescription,
+        AddressSize,
+    },
+};
+
+pub const IR_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize)]
+pub struct IrFile<'a> {
+    pub ir_version: u32,
+    pub description: IrDescription<'a>,
+    pub groups: Vec<IrGroup<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IrDescription<'a> {
+    pub name: &'a str,
+    pub description: Option<&'a str>,
+    pub version: String,
+    pub extension: Option<String>,
+    pub index_size: u8,
+    pub address_size: Option<u8>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IrGroup<'a> {
+    pub name: Option<&'a str>,
+    pub registers: Vec<IrRegister<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IrRegister<'a> {
+    pub name: &'a str,
+    pub description: Option<&'a str>,
+    pub access: String,
+    pub size: u8,
+    pub read_location: IrLocation,
+    pub write_location: IrLocation,
+    pub restore_order: Option<i64>,
+    pub bit_fields: Vec<IrBitField<'a>>,
+    pub enums: Vec<IrEnum<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IrLocation {
+    pub kind: &'static str,
+    pub value: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IrBitField<'a> {
+    pub msb: u16,
+    pub lsb: u16,
+    pub reserved: bool,
+    pub name: Option<&'a str>,
+    pub description: Option<&'a str>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IrEnum<'a> {
+    pub name: &'a str,
+    pub description: Option<&'a str>,
+    pub msb: u16,
+    pub lsb: u16,
+    pub all_possible_values_are_defined: bool,
+    pub values: Vec<IrEnumValue<'a>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IrEnumValue<'a> {
+    pub value: u64,
+    pub name: &'a str,
+    pub description: Option<&'a str>,
+}
+
+impl <'a> IrFile<'a> {
+    pub fn new(parsed_file: &'a ParsedFile) -> Self {
+        let groups = parsed_file.register_groups()
+            .into_iter()
+            .map(|(name, registers)| IrGroup::new(name, registers))
+            .collect();
+
+        Self {
+            ir_version: IR_VERSION,
+            description: IrDescription::new(&parsed_file.description),
+            groups,
+        }
+    }
+}
+
+impl <'a> IrDescription<'a> {
+    fn new(rd: &'a RegisterDescription) -> Self {
+        let address_size = match rd.address_size {
+            AddressSize::Pointer => None,
+            AddressSize::RegisterSize(size) => Some(size as u8),
+        };
+
+        Self {
+            name: rd.name.as_str(),
+            description: rd.description.as_deref(),
+            version: rd.version.to_string(),
+            extension: rd.extension.map(|x| x.to_string()),
+            index_size: rd.index_size as u8,
+            address_size,
+        }
+    }
+}
+
+impl <'a> IrGroup<'a> {
+    fn new(name: Option<&'a str>, registers: &'a [Register]) -> Self {
+        Self {
+            name,
+            registers: registers.iter().map(IrRegister::new).collect(),
+        }
+    }
+}
+
+impl <'a> IrRegister<'a> {
+    fn new(r: &'a Register) -> Self {
+        Self {
+            name: r.name.as_str(),
+            description: r.description.as_deref(),
+            access: r.access_mode.to_string(),
+            size: r.size_in_bits as u8,
+            read_location: IrLocation::new(r.read_location),
+            write_location: IrLocation::new(r.write_location),
+            restore_order: r.restore_order,
+            bit_fields: r.functions.iter().map(IrBitField::new).collect(),
+            enums: r.enums.iter().map(IrEnum::new).collect(),
+        }
+    }
+}
+
+impl IrLocation {
+    fn new(location: RegisterLocation) -> Self {
+        let (kind, value) = match location {
+            RegisterLocation::Index(value) => ("index", value),
+            RegisterLocation::Relative(value) => ("relative", value),
+            RegisterLocation::Absolute(value) => ("absolute", value),
+        };
+
+        Self {
+            kind,
+            value,
+        }
+    }
+}
+
+impl <'a> IrBitField<'a> {
+    fn new(f: &'a RegisterFunction) -> Self {
+        Self {
+            msb: f.range.msb,
+            lsb: f.range.lsb,
+            reserved: f.status.is_reserved(),
+            name: f.name(),
+            description: f.description(),
+        }
+    }
+}
+
+impl <'a> IrEnum<'a> {
+    fn new(e: &'a RegisterEnum) -> Self {
+        Self {
+            name: e.name.as_str(),
+            description: e.description.as_deref(),
+            msb: e.range.msb,
+            lsb: e.range.lsb,
+            all_possible_values_are_defined: e.all_possible_values_are_defined,
+            values: e.values.iter().map(IrEnumValue::new).collect(),
+        }
+    }
+}
+
+impl <'a> IrEnumValue<'a> {
+    fn new(v: &'a RegisterEnumValue) -> Self {
+        Self {
+            value: v.value,
+            name: v.name.as_str(),
+            description: v.description.as_deref(),
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! Register descriptions and helpers for code generator, export and
+//! import tests.
+
+use super::{
+    ir::IrFile,
+    validation::{self, ParsedFile},
+};
+
+/// Registers with absolute locations, so that every code generator
+/// and export format supports the description.
+pub const DEVICE: &str = r#"
+[register_description]
+name = "Device"
+version = "0.1"
+description = "Test device"
+default_register_size = "32"
+default_register_access = "rw"
+extension = "vga"
+index_size = "8"
+address_size = "32"
+
+[[register.timer]]
+name = "Control"
+description = "Timer control"
+absolute_address = 0x40000000
+bit_fields = [
+    { bit = "31:12", reserved = true },
+    { bit = "11:8", name = "Prescaler" },
+    { bit = "7:4", name = "Mode", description = "Timer mode" },
+    { bit = "3:1", reserved = true },
+    { bit = "0", name = "Enable" },
+]
+
+[[register.timer.enum]]
+name = "Mode"
+bit = "7:4"
+values = [
+    { value = 0, name = "One Shot", description = "Stop after one period" },
+    { value = 1, name = "Periodic" },
+]
+
+[[register.timer]]
+name = "Count"
+absolute_address = 0x40000004
+access = "r"
+size = "16"
+bit_fields = [
+    { bit = "15:0", name = "Value" },
+]
+
+[[register.uart]]
+name = "Data"
+absolute_address = 0x40001000
+access = "w"
+size = "8"
+bit_fields = [
+    { bit = "7:6", name = "Channel" },
+    { bit = "5:0", name = "Byte" },
+]
+
+[[register.uart]]
+name = "Config"
+absolute_address = 0x40001004
+size = "8"
+bit_fields = [
+    { bit = "7:1", reserved = true },
+    { bit = "0", name = "Parity" },
+]
+"#;
+
+/// Panics if the description is not valid.
+pub fn parsed_file(text: &str) -> ParsedFile {
+    let root_table: toml::value::Table = toml::from_str(text).unwrap();
+
+    match validation::check_root_table(root_table) {
+        Ok(parsed_file) => parsed_file,
+        Err(errors) => {
+            let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+            panic!("invalid register description:\n{}", errors.join("\n"))
+        }
+    }
+}
+
+/// Register groups in the intermediate representation which is
+/// used for comparing parsed files.
+pub fn ir_groups(parsed_file: &ParsedFile) -> serde_json::Value {
+    serde_json::to_value(&IrFile::new(parsed_file).groups).unwrap()
+}
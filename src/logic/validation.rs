@@ -59,6 +59,24 @@ pub struct ParsedFile {
     pub registers: Option<Registers>,
 }
 
+impl ParsedFile {
+    /// Registers by group. Name of the group is `None`
+    /// if registers are not grouped.
+    pub fn register_groups(&self) -> Vec<(Option<&str>, &[Register])> {
+        match &self.registers {
+            None => vec![],
+            Some(Registers::Groups(groups)) => {
+                groups.iter().map(|(name, registers)| (Some(name.as_str()), registers.as_slice())).collect()
+            }
+            Some(Registers::OnlyRegisters(registers)) => vec![(None, registers.as_slice())],
+        }
+    }
+
+    pub fn all_registers(&self) -> impl Iterator<Item=&Register> {
+        self.register_groups().into_iter().flat_map(|(_, registers)| registers.iter())
+    }
+}
+
 #[derive(Debug)]
 pub enum Registers {
     Groups(Vec<(String, Vec<Register>)>),
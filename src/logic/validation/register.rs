@@ -19,7 +19,7 @@ use super::{
 };
 
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Clone)]
 pub enum RegisterSize {
     Size8 = 8,
     Size16 = 16,
@@ -127,6 +127,41 @@ impl BitRange {
 
         Ok(max_value)
     }
+
+    /// Fills bits of a register which are not covered by `ranges` with
+    /// new ranges. `ranges` must be ordered from the most significant
+    /// range to the least significant range.
+    ///
+    /// Returns ranges from the most significant to the least significant.
+    /// Existing ranges have the index of the range in `ranges`, new ranges
+    /// have `None`.
+    pub fn fill_gaps(ranges: &[BitRange], size: RegisterSize) -> Vec<(BitRange, Option<usize>)> {
+        let mut new_ranges = vec![];
+        // Most significant bit which is not yet covered by a range.
+        let mut current_msb = Some(size as u16 - 1);
+
+        for (i, range) in ranges.iter().enumerate() {
+            if let Some(msb) = current_msb {
+                if msb > range.msb {
+                    new_ranges.push((BitRange::new(msb, range.msb + 1), None));
+                }
+
+                if range.lsb == 0 {
+                    current_msb = None;
+                } else if range.lsb <= msb {
+                    current_msb = Some(range.lsb - 1);
+                }
+            }
+
+            new_ranges.push((*range, Some(i)));
+        }
+
+        if let Some(msb) = current_msb {
+            new_ranges.push((BitRange::new(msb, 0), None));
+        }
+
+        new_ranges
+    }
 }
 
 impl fmt::Display for BitRange {
@@ -266,6 +301,9 @@ pub struct Register {
     pub functions: Vec<RegisterFunction>,
     pub enums: Vec<RegisterEnum>,
     pub index: Option<u16>,
+    /// Ordering hint for restoring saved register values. Registers are
+    /// restored in ascending order. Default value is 0.
+    pub restore_order: Option<i64>,
 }
 
 impl Register {
@@ -467,6 +505,7 @@ const SIZE_IN_BITS_KEY: &str = "size";
 const WRITE_INDEX_KEY: &str = "index_w";
 const WRITE_ABSOLUTE_ADDRESS_KEY: &str = "absolute_address_w";
 const WRITE_RELATIVE_ADDRESS_KEY: &str = "relative_address_w";
+const RESTORE_ORDER_KEY: &str = "restore_order";
 
 const POSSIBLE_KEYS_REGISTER: &[&str] = &[
     NAME_KEY,
@@ -481,6 +520,7 @@ const POSSIBLE_KEYS_REGISTER: &[&str] = &[
     WRITE_INDEX_KEY,
     WRITE_ABSOLUTE_ADDRESS_KEY,
     WRITE_RELATIVE_ADDRESS_KEY,
+    RESTORE_ORDER_KEY,
 ];
 
 const POSSIBLE_KEYS_FUNCTION: &[&str] = &[
@@ -582,6 +622,7 @@ pub fn validate_register_table(
 
 
     let index = v.u16(INDEX_KEY).optional()?;
+    let restore_order = v.integer(RESTORE_ORDER_KEY).optional()?;
 
     let mut register = Register {
         name,
@@ -593,6 +634,7 @@ pub fn validate_register_table(
         functions,
         enums,
         index,
+        restore_order,
     };
 
     register.check_functions(&mut v);
@@ -679,3 +721,27 @@ pub fn validate_enum_value_table(
         description,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fill_gaps_between_ranges() {
+        let ranges = [BitRange::new(6, 5), BitRange::new(2, 2)];
+        let filled = BitRange::fill_gaps(&ranges, RegisterSize::Size8);
+        assert_eq!(filled, vec![
+            (BitRange::new(7, 7), None),
+            (BitRange::new(6, 5), Some(0)),
+            (BitRange::new(4, 3), None),
+            (BitRange::new(2, 2), Some(1)),
+            (BitRange::new(1, 0), None),
+        ]);
+    }
+
+    #[test]
+    fn fill_gaps_without_ranges() {
+        let filled = BitRange::fill_gaps(&[], RegisterSize::Size16);
+        assert_eq!(filled, vec![(BitRange::new(15, 0), None)]);
+    }
+}
@@ -139,40 +139,18 @@ fn fill_empty_register_fields_as_reserved(mut s: &mut Cursive) {
     let _ = modify_ui_and_data(&mut s, |mut s, editor_data| {
         let bit_fields = &mut editor_data.objects.register.functions;
         let register_size = editor_data.objects.register.size.value;
-        let mut new_fields: Vec<UiFunction> = vec![];
 
-        let mut current_msb = register_size as u16 - 1;
-        let mut end_reached = false;
+        let mut ranges = vec![];
         for ui_field in bit_fields.iter() {
-            if end_reached {
-                new_fields.push(ui_field.clone());
-                continue;
-            }
-
-            let range = super::field::error_message(&mut s, BitRange::try_from(ui_field.bit.value.as_str().trim()))?;
-
-            if current_msb > range.msb {
-                let new_range = BitRange::new(current_msb, range.msb + 1);
-                let new_ui_field = UiFunction::new_reserved(&new_range.to_string());
-                new_fields.push(new_ui_field);
-            } else if current_msb < range.msb {
-                end_reached = true;
-            }
-
-            new_fields.push(ui_field.clone());
-
-            if range.lsb == 0 {
-                end_reached = true;
-            } else {
-                current_msb = range.lsb - 1;
-            }
+            ranges.push(super::field::error_message(&mut s, BitRange::try_from(ui_field.bit.value.as_str().trim()))?);
         }
 
-        if !end_reached {
-            let new_range = BitRange::new(current_msb, 0);
-            let new_ui_field = UiFunction::new_reserved(&new_range.to_string());
-            new_fields.push(new_ui_field);
-        }
+        let new_fields: Vec<UiFunction> = BitRange::fill_gaps(&ranges, register_size).into_iter().map(|(range, i)| {
+            match i {
+                Some(i) => bit_fields[i].clone(),
+                None => UiFunction::new_reserved(&range.to_string()),
+            }
+        }).collect();
 
         *bit_fields = new_fields;
 